@@ -1,21 +1,29 @@
-use tauri::Window;
+use tauri::{Manager, Window};
 use crate::DeepLinkPayload;
+use crate::models::state::LaunchState;
 #[allow(non_snake_case)]
 #[tauri::command]
 pub fn debug_deep_link(window: Window, guideId: String, authToken: String, totalSteps: i32) {
-    println!("Debug deep link called with params:");
-    println!("  guideId: {}", guideId);
-    println!("  authToken: {}", authToken);
-    println!("  totalSteps: {}", totalSteps);
+    // The auth token stays out of the logs; they end up in support tickets
+    tracing::debug!(guide_id = %guideId, total_steps = totalSteps, "Debug deep link called");
 
     let payload = DeepLinkPayload {
         guide_id: guideId,
         auth_token: authToken,
         total_steps: totalSteps,
     };
+    window.state::<LaunchState>().set(payload.clone());
     
     match window.emit("deep-link-payload", payload) {
-        Ok(_) => println!("Successfully emitted deep-link-payload event"),
-        Err(e) => println!("Failed to emit deep-link-payload event: {}", e),
+        Ok(_) => tracing::debug!("Emitted deep-link-payload event"),
+        Err(e) => tracing::error!(error = %e, "Failed to emit deep-link-payload event"),
     }
-}
\ No newline at end of file
+}
+
+const DEFAULT_LOG_LINES: usize = 500;
+
+/// Recent log lines to attach to support tickets.
+#[tauri::command]
+pub async fn get_recent_logs(max_lines: Option<usize>) -> Result<Vec<String>, String> {
+    crate::logging::recent_lines(max_lines.unwrap_or(DEFAULT_LOG_LINES)).map_err(|e| e.to_string())
+}
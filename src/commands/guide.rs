@@ -1,9 +1,30 @@
 use crate::models::Guide;
+use crate::models::element_info::ElementInfo;
+use crate::models::selector::ElementSelector;
+use crate::ui_automation::automation::Automation;
+use crate::ui_automation::selector::locate_element;
 use tauri::command;
+use windows::Win32::System::Com::{CoInitializeEx, COINIT_MULTITHREADED};
 
 #[command]
 pub async fn load_guides() -> Result<Vec<Guide>, String> {
     // For now, return an empty vector
     // Later you can implement actual guide loading from file/database
     Ok(Vec::new())
-}
\ No newline at end of file
+}
+
+/// Finds a recorded step's element in the live UI, e.g. to check a guide
+/// still matches the app or to highlight where to click. `None` if it's gone.
+#[command]
+pub async fn locate_step_element(selector: ElementSelector) -> Result<Option<ElementInfo>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        unsafe {
+            // Blocking threads are pooled, so this may already have been done
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+        }
+        let automation = Automation::new().map_err(|e| e.to_string())?;
+        Ok(locate_element(&automation, &selector))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
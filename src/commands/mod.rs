@@ -1,3 +1,6 @@
 pub mod tracking;
 pub mod guide;
 pub mod debug;  // Add this line
+pub mod settings;
+pub mod recovery;
+pub mod scope;
\ No newline at end of file
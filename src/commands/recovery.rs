@@ -0,0 +1,101 @@
+use tauri::{State, Window};
+use crate::journal::{self, JournaledSession, SessionSummary};
+use crate::models::{Guide, GuideStep};
+use crate::models::state::{TrackingState, STATE_STOPPED};
+use super::tracking::{journal_session, run_countdown};
+
+fn ensure_not_active(tracking_state: &TrackingState, session_id: &str) -> Result<(), String> {
+    if tracking_state.with_session(|session| session.id == session_id).unwrap_or(false) {
+        return Err("Session is still being recorded".to_string());
+    }
+    Ok(())
+}
+
+/// Sessions left open by a crash, for the recovery prompt on startup.
+#[tauri::command]
+pub async fn list_interrupted_sessions(
+    tracking_state: State<'_, TrackingState>,
+) -> Result<Vec<SessionSummary>, String> {
+    let active_id = tracking_state.with_session(|session| session.id.clone());
+    let sessions = journal::list_interrupted_sessions().map_err(|e| e.to_string())?;
+    Ok(sessions.into_iter()
+        .filter(|summary| Some(&summary.session.id) != active_id.as_ref())
+        .collect())
+}
+
+/// Continues an interrupted session where it stopped. The journaled steps
+/// are returned so the frontend can rebuild its step list.
+#[tauri::command]
+pub async fn resume_interrupted_session(
+    window: Window,
+    tracking_state: State<'_, TrackingState>,
+    session_id: String,
+) -> Result<JournaledSession, String> {
+    if tracking_state.get_state() != STATE_STOPPED {
+        return Err("A recording is already in progress".to_string());
+    }
+
+    let mut journaled = journal::load_session(&session_id).map_err(|e| e.to_string())?;
+    // The time since the crash and the countdown aren't recording time, so
+    // they're a pause from the last step on (a crash while paused already
+    // left one open). It closes once the countdown ends, like a resume.
+    let last_step = journaled.steps.last().map(|step| step.timestamp.clone()).unwrap_or_default();
+    journaled.session.pause_since(&last_step);
+    journal_session(&journaled.session);
+    tracing::info!(session_id = %journaled.session.id, last_sequence = journaled.session.last_sequence, "Resuming interrupted recording session");
+
+    tracking_state.restore_session(journaled.session.clone());
+    window.emit("session-resumed", &journaled.session).map_err(|e| e.to_string())?;
+    window.emit("recording-mode", true).map_err(|e| e.to_string())?;
+    window.minimize().map_err(|e| e.to_string())?;
+    run_countdown(&window, &tracking_state, |_, tracking_state| {
+        let session = tracking_state.with_session(|session| {
+            session.resume();
+            session.clone()
+        });
+        if let Some(session) = session {
+            journal_session(&session);
+        }
+        Ok(())
+    })?;
+    Ok(journaled)
+}
+
+/// Closes an interrupted session and turns its steps into a guide.
+#[tauri::command]
+pub async fn recover_session_as_guide(
+    tracking_state: State<'_, TrackingState>,
+    session_id: String,
+    title: Option<String>,
+) -> Result<Guide, String> {
+    ensure_not_active(&tracking_state, &session_id)?;
+
+    let JournaledSession { mut session, steps } = journal::load_session(&session_id).map_err(|e| e.to_string())?;
+    session.stop();
+    journal_session(&session);
+
+    let steps = steps.into_iter()
+        .enumerate()
+        .map(|(index, element)| GuideStep {
+            sequence: index as i32 + 1,
+            element,
+            step_description: String::new(),
+        })
+        .collect();
+
+    Ok(Guide {
+        title: title.unwrap_or_else(|| format!("Recovered recording {}", session.started_at)),
+        description: String::new(),
+        steps,
+        created_at: session.started_at,
+    })
+}
+
+#[tauri::command]
+pub async fn discard_interrupted_session(
+    tracking_state: State<'_, TrackingState>,
+    session_id: String,
+) -> Result<(), String> {
+    ensure_not_active(&tracking_state, &session_id)?;
+    journal::delete_session(&session_id).map_err(|e| e.to_string())
+}
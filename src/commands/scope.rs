@@ -0,0 +1,36 @@
+use tauri::{State, Window};
+use crate::models::session::TargetScope;
+use crate::models::state::TrackingState;
+use crate::ui_automation::window::{list_top_level_windows, WindowContext};
+use super::tracking::journal_session;
+
+pub fn validate_scope(scope: Option<TargetScope>) -> Result<Option<TargetScope>, String> {
+    match scope {
+        Some(scope) if scope.is_empty() => Err("Target scope needs at least one process or window".to_string()),
+        scope => Ok(scope),
+    }
+}
+
+#[tauri::command]
+pub async fn list_open_windows() -> Result<Vec<WindowContext>, String> {
+    Ok(list_top_level_windows())
+}
+
+/// Changes what the current recording is scoped to; `None` records
+/// everything again.
+#[tauri::command]
+pub async fn set_target_scope(
+    window: Window,
+    tracking_state: State<'_, TrackingState>,
+    scope: Option<TargetScope>,
+) -> Result<Option<TargetScope>, String> {
+    let scope = validate_scope(scope)?;
+    let session = tracking_state.with_session(|session| {
+        session.scope = scope;
+        session.clone()
+    }).ok_or_else(|| "No recording in progress".to_string())?;
+    journal_session(&session);
+
+    window.emit("session-updated", &session).map_err(|e| e.to_string())?;
+    Ok(session.scope)
+}
@@ -0,0 +1,162 @@
+use tauri::State;
+use crate::models::settings::{default_exclusion_rules, ExclusionRule, CountdownConfig, HotkeyConfig, HoverConfig, IdleConfig, NavigationConfig, RedactionConfig};
+use crate::models::state::SettingsState;
+use crate::tracking::chord::Chord;
+use crate::tracking::exclusion::ExclusionFilter;
+use crate::tracking::hotkeys::HotkeyBindings;
+
+// Shorter dwells turn ordinary pointer movement into hover steps
+const MIN_HOVER_DWELL_MS: u64 = 300;
+const MAX_COUNTDOWN_SECS: u64 = 10;
+
+fn normalize_chord(value: &str) -> Result<String, String> {
+    Ok(value.parse::<Chord>()?.to_string())
+}
+
+#[tauri::command]
+pub async fn get_hotkeys(
+    settings_state: State<'_, SettingsState>,
+) -> Result<HotkeyConfig, String> {
+    Ok(settings_state.get().hotkeys)
+}
+
+/// Validates and saves the hotkeys, returning them in normalized form
+/// ("alt+;" comes back as "Alt+;").
+#[tauri::command]
+pub async fn update_hotkeys(
+    settings_state: State<'_, SettingsState>,
+    hotkeys: HotkeyConfig,
+) -> Result<HotkeyConfig, String> {
+    HotkeyBindings::try_from(&hotkeys)?;
+    let hotkeys = HotkeyConfig {
+        capture: normalize_chord(&hotkeys.capture)?,
+        pause_resume: normalize_chord(&hotkeys.pause_resume)?,
+        stop: normalize_chord(&hotkeys.stop)?,
+        discard_last_step: normalize_chord(&hotkeys.discard_last_step)?,
+    };
+
+    let settings = settings_state.update(|settings| settings.hotkeys = hotkeys)?;
+    Ok(settings.hotkeys)
+}
+
+#[tauri::command]
+pub async fn reset_hotkeys(
+    settings_state: State<'_, SettingsState>,
+) -> Result<HotkeyConfig, String> {
+    let settings = settings_state.update(|settings| settings.hotkeys = HotkeyConfig::default())?;
+    Ok(settings.hotkeys)
+}
+
+#[tauri::command]
+pub async fn get_exclusion_rules(
+    settings_state: State<'_, SettingsState>,
+) -> Result<Vec<ExclusionRule>, String> {
+    Ok(settings_state.get().exclusions)
+}
+
+#[tauri::command]
+pub async fn update_exclusion_rules(
+    settings_state: State<'_, SettingsState>,
+    rules: Vec<ExclusionRule>,
+) -> Result<Vec<ExclusionRule>, String> {
+    ExclusionFilter::new(&rules)?;
+    let settings = settings_state.update(|settings| settings.exclusions = rules)?;
+    Ok(settings.exclusions)
+}
+
+#[tauri::command]
+pub async fn reset_exclusion_rules(
+    settings_state: State<'_, SettingsState>,
+) -> Result<Vec<ExclusionRule>, String> {
+    let settings = settings_state.update(|settings| settings.exclusions = default_exclusion_rules())?;
+    Ok(settings.exclusions)
+}
+
+#[tauri::command]
+pub async fn get_idle_settings(
+    settings_state: State<'_, SettingsState>,
+) -> Result<IdleConfig, String> {
+    Ok(settings_state.get().idle)
+}
+
+#[tauri::command]
+pub async fn update_idle_settings(
+    settings_state: State<'_, SettingsState>,
+    idle: IdleConfig,
+) -> Result<IdleConfig, String> {
+    if idle.timeout_secs == 0 {
+        return Err("Idle timeout must be at least one second".to_string());
+    }
+    let settings = settings_state.update(|settings| settings.idle = idle)?;
+    Ok(settings.idle)
+}
+
+#[tauri::command]
+pub async fn get_hover_settings(
+    settings_state: State<'_, SettingsState>,
+) -> Result<HoverConfig, String> {
+    Ok(settings_state.get().hover)
+}
+
+#[tauri::command]
+pub async fn update_hover_settings(
+    settings_state: State<'_, SettingsState>,
+    hover: HoverConfig,
+) -> Result<HoverConfig, String> {
+    if hover.dwell_ms < MIN_HOVER_DWELL_MS {
+        return Err(format!("Hover dwell must be at least {} ms", MIN_HOVER_DWELL_MS));
+    }
+    let settings = settings_state.update(|settings| settings.hover = hover)?;
+    Ok(settings.hover)
+}
+
+#[tauri::command]
+pub async fn get_countdown_settings(
+    settings_state: State<'_, SettingsState>,
+) -> Result<CountdownConfig, String> {
+    Ok(settings_state.get().countdown)
+}
+
+#[tauri::command]
+pub async fn update_countdown_settings(
+    settings_state: State<'_, SettingsState>,
+    countdown: CountdownConfig,
+) -> Result<CountdownConfig, String> {
+    if countdown.seconds > MAX_COUNTDOWN_SECS {
+        return Err(format!("Countdown can be at most {} seconds", MAX_COUNTDOWN_SECS));
+    }
+    let settings = settings_state.update(|settings| settings.countdown = countdown)?;
+    Ok(settings.countdown)
+}
+
+#[tauri::command]
+pub async fn get_redaction_settings(
+    settings_state: State<'_, SettingsState>,
+) -> Result<RedactionConfig, String> {
+    Ok(settings_state.get().redaction)
+}
+
+#[tauri::command]
+pub async fn update_redaction_settings(
+    settings_state: State<'_, SettingsState>,
+    redaction: RedactionConfig,
+) -> Result<RedactionConfig, String> {
+    let settings = settings_state.update(|settings| settings.redaction = redaction)?;
+    Ok(settings.redaction)
+}
+
+#[tauri::command]
+pub async fn get_navigation_settings(
+    settings_state: State<'_, SettingsState>,
+) -> Result<NavigationConfig, String> {
+    Ok(settings_state.get().navigation)
+}
+
+#[tauri::command]
+pub async fn update_navigation_settings(
+    settings_state: State<'_, SettingsState>,
+    navigation: NavigationConfig,
+) -> Result<NavigationConfig, String> {
+    let settings = settings_state.update(|settings| settings.navigation = navigation)?;
+    Ok(settings.navigation)
+}
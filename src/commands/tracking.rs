@@ -1,5 +1,16 @@
-use tauri::{Window, State, Size, PhysicalSize, Position, PhysicalPosition};
-use crate::models::state::{TrackingState, WindowState, STATE_RUNNING, STATE_PAUSED, STATE_STOPPED};
+use std::time::Duration;
+use tauri::{Manager, Window, State, Size, PhysicalSize, Position, PhysicalPosition};
+use crate::journal;
+use crate::models::session::{CountdownTick, RecordingSession, StepProgress, TargetScope};
+use crate::models::state::{
+    TrackingState, WindowState, LaunchState, SettingsState, STATE_RUNNING, STATE_PAUSED, STATE_STOPPED, STATE_COUNTDOWN,
+};
+use super::scope::validate_scope;
+use crate::tracking::pipeline::{PipelineMetrics, PipelineStats};
+
+// How long stopping waits for captured steps to finish resolving
+const STOP_DRAIN_TIMEOUT: Duration = Duration::from_secs(3);
+const COUNTDOWN_TICK: Duration = Duration::from_secs(1);
 
 #[tauri::command]
 pub async fn enter_compact_mode(
@@ -46,31 +57,157 @@ pub async fn toggle_pause(
     
     match current_state {
         STATE_RUNNING => {
-            tracking_state.set_state(STATE_PAUSED);
-            window.emit("recording-paused", true).map_err(|e| e.to_string())?;
-            window.unminimize().map_err(|e| e.to_string())?;
-            window.show().map_err(|e| e.to_string())?;
+            pause_recording(&window, &tracking_state)?;
             Ok(true)
         },
         STATE_PAUSED => {
-            tracking_state.set_state(STATE_RUNNING);
-            window.emit("recording-paused", false).map_err(|e| e.to_string())?;
-            window.minimize().map_err(|e| e.to_string())?;
+            resume_recording(&window, &tracking_state)?;
             Ok(false)
         },
         _ => Ok(false),
     }
 }
 
+/// Journal failures are logged rather than stopping the recording; the
+/// frontend still has the steps.
+pub fn journal_session(session: &RecordingSession) {
+    if let Err(e) = journal::save_session(session) {
+        tracing::error!(session_id = %session.id, error = %e, "Failed to journal session");
+    }
+}
+
+/// Moves between running and paused and reports it, without touching the
+/// window. Idle detection uses this directly.
+pub fn set_paused(window: &Window, tracking_state: &TrackingState, paused: bool) -> Result<(), String> {
+    tracking_state.set_state(if paused { STATE_PAUSED } else { STATE_RUNNING });
+    report_paused(window, tracking_state, paused)
+}
+
+// Updates the session and the frontend once the state has changed
+fn report_paused(window: &Window, tracking_state: &TrackingState, paused: bool) -> Result<(), String> {
+    if let Some(session) = tracking_state.with_session(|session| {
+        if paused { session.pause() } else { session.resume() }
+        session.clone()
+    }) {
+        journal_session(&session);
+        let event = if paused { "session-paused" } else { "session-resumed" };
+        window.emit(event, session).map_err(|e| e.to_string())?;
+    }
+    window.emit("recording-paused", paused).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Starts running after the configured countdown, emitting
+/// `recording-countdown` every second. Input is ignored meanwhile and a stop
+/// cancels it; `on_running` runs once the state is running.
+pub fn run_countdown<F>(window: &Window, tracking_state: &TrackingState, on_running: F) -> Result<(), String>
+where
+    F: FnOnce(&Window, &TrackingState) -> Result<(), String> + Send + 'static,
+{
+    let seconds = window.state::<SettingsState>().get().countdown.seconds;
+    if seconds == 0 {
+        tracking_state.set_state(STATE_RUNNING);
+        return on_running(window, tracking_state);
+    }
+
+    let session_id = tracking_state.with_session(|session| session.id.clone());
+    tracking_state.set_state(STATE_COUNTDOWN);
+    let window = window.clone();
+    let tracking_state = tracking_state.clone();
+    std::thread::spawn(move || {
+        let emit_tick = |remaining_secs: u64| {
+            let tick = CountdownTick { session_id: session_id.clone(), remaining_secs };
+            if let Err(e) = window.emit("recording-countdown", tick) {
+                tracing::warn!(error = %e, "Failed to emit countdown tick");
+            }
+        };
+        // A stop, or a stop and a new start, ends this countdown
+        let counting = || tracking_state.get_state() == STATE_COUNTDOWN
+            && tracking_state.with_session(|session| session.id.clone()) == session_id;
+
+        for remaining_secs in (1..=seconds).rev() {
+            if !counting() {
+                tracing::info!("Countdown cancelled");
+                return;
+            }
+            emit_tick(remaining_secs);
+            std::thread::sleep(COUNTDOWN_TICK);
+        }
+        if !counting() || !tracking_state.transition(STATE_COUNTDOWN, STATE_RUNNING) {
+            tracing::info!("Countdown cancelled");
+            return;
+        }
+        emit_tick(0);
+        if let Err(e) = on_running(&window, &tracking_state) {
+            tracing::error!(error = %e, "Failed to report recording start");
+        }
+    });
+    Ok(())
+}
+
+// Shared with the tracking loop so hotkeys behave exactly like the buttons
+pub fn pause_recording(window: &Window, tracking_state: &TrackingState) -> Result<(), String> {
+    set_paused(window, tracking_state, true)?;
+    window.unminimize().map_err(|e| e.to_string())?;
+    window.show().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Resumes after the countdown; the session stays paused until it ends.
+pub fn resume_recording(window: &Window, tracking_state: &TrackingState) -> Result<(), String> {
+    window.minimize().map_err(|e| e.to_string())?;
+    run_countdown(window, tracking_state, |window, tracking_state| report_paused(window, tracking_state, false))
+}
+
 #[tauri::command]
 pub async fn start_tracking(
     window: Window,
     tracking_state: State<'_, TrackingState>,
+    launch_state: State<'_, LaunchState>,
+    guide_id: Option<String>,
+    step_limit: Option<u64>,
+    scope: Option<TargetScope>,
 ) -> Result<(), String> {
-    tracking_state.set_state(STATE_RUNNING);
+    let scope = validate_scope(scope)?;
+    // Fall back to the guide we were launched for
+    let launch = launch_state.get();
+    let guide_id = guide_id.or_else(|| launch.as_ref().map(|payload| payload.guide_id.clone()))
+        .filter(|id| !id.is_empty());
+    let step_limit = step_limit.or_else(|| launch.as_ref().map(|payload| payload.total_steps.max(0) as u64))
+        .filter(|&limit| limit > 0);
+    let session = tracking_state.start_session(guide_id, step_limit, scope);
+    tracing::info!(session_id = %session.id, guide_id = ?session.guide_id, step_limit = ?session.step_limit, "Started recording session");
+    journal_session(&session);
+
+    window.emit("recording-progress", session.progress()).map_err(|e| e.to_string())?;
+    window.emit("session-started", session).map_err(|e| e.to_string())?;
     window.emit("recording-mode", true).map_err(|e| e.to_string())?;
     window.minimize().map_err(|e| e.to_string())?;
-    Ok(())
+    run_countdown(&window, &tracking_state, |_, _| Ok(()))
+}
+
+/// Changes the step limit of the current recording; `None` removes it.
+#[tauri::command]
+pub async fn set_step_limit(
+    window: Window,
+    tracking_state: State<'_, TrackingState>,
+    step_limit: Option<u64>,
+) -> Result<StepProgress, String> {
+    let session = tracking_state.with_session(|session| {
+        session.step_limit = step_limit.filter(|&limit| limit > 0);
+        session.clone()
+    }).ok_or_else(|| "No recording in progress".to_string())?;
+    journal_session(&session);
+
+    let progress = session.progress();
+    window.emit("recording-progress", &progress).map_err(|e| e.to_string())?;
+    // Lowering the limit below what's already recorded ends the recording
+    if session.limit_reached() && tracking_state.get_state() != STATE_STOPPED {
+        window.emit("recording-limit-reached", &progress).map_err(|e| e.to_string())?;
+        let window_state = window.state::<WindowState>();
+        finish_recording(&window, &tracking_state, &window_state)?;
+    }
+    Ok(progress)
 }
 
 #[tauri::command]
@@ -78,8 +215,24 @@ pub async fn stop_tracking(
     window: Window,
     tracking_state: State<'_, TrackingState>,
     window_state: State<'_, WindowState>,
+) -> Result<(), String> {
+    finish_recording(&window, &tracking_state, &window_state)
+}
+
+pub fn finish_recording(
+    window: &Window,
+    tracking_state: &TrackingState,
+    window_state: &WindowState,
 ) -> Result<(), String> {
     tracking_state.set_state(STATE_STOPPED);
+    if !tracking_state.wait_for_steps(STOP_DRAIN_TIMEOUT) {
+        tracing::warn!("Stopping with steps still in the pipeline");
+    }
+    if let Some(session) = tracking_state.stop_session() {
+        tracing::info!(session_id = %session.id, steps = session.recorded_steps(), "Stopped recording session");
+        journal_session(&session);
+        window.emit("session-stopped", session).map_err(|e| e.to_string())?;
+    }
 
     window.unminimize().map_err(|e| e.to_string())?;
     window.show().map_err(|e| e.to_string())?;
@@ -110,4 +263,11 @@ pub async fn stop_tracking(
     
     window.emit("recording-mode", false).map_err(|e| e.to_string())?;
     Ok(())
+}
+
+#[tauri::command]
+pub async fn get_pipeline_metrics(
+    metrics: State<'_, PipelineMetrics>,
+) -> Result<PipelineStats, String> {
+    Ok(metrics.snapshot())
 }
\ No newline at end of file
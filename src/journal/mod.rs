@@ -0,0 +1,218 @@
+// Append-only record of each recording session, so steps survive a crash.
+//
+// Every session gets a folder under `MataPass/sessions/<id>` holding
+// `session.json` (the latest `RecordingSession` snapshot), `steps.jsonl`
+// (one entry per line) and `screenshots/<sequence>.jpg`. Everything is
+// synced to disk before the step is emitted to the frontend.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use serde::{Serialize, Deserialize};
+use dirs;
+
+use crate::models::element_info::ElementInfo;
+use crate::models::session::RecordingSession;
+
+/// Closed sessions are kept this long in case the frontend lost them too
+const CLOSED_SESSION_RETENTION_DAYS: i64 = 7;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+enum JournalEntry {
+    Step {
+        step: Box<ElementInfo>,
+        screenshot_file: Option<String>,
+    },
+    Discarded {
+        sequence: u64,
+    },
+}
+
+/// A session read back from disk, with screenshots inlined again and
+/// discarded steps removed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournaledSession {
+    pub session: RecordingSession,
+    pub steps: Vec<ElementInfo>,
+}
+
+fn sessions_dir() -> io::Result<PathBuf> {
+    let dir = dirs::data_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Failed to get app data directory"))?;
+    Ok(dir.join("MataPass").join("sessions"))
+}
+
+fn session_dir(session_id: &str) -> io::Result<PathBuf> {
+    // Ids come back from the frontend, don't let them escape the folder
+    if session_id.is_empty() || session_id.contains(['/', '\\', '.']) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid session id '{}'", session_id)));
+    }
+    Ok(sessions_dir()?.join(session_id))
+}
+
+fn write_synced(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+/// Replaces the file at `path` so a crash leaves either the old contents
+/// or the new ones, never a truncated file. Goes through `<name>.tmp`
+/// next to it.
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    write_synced(&temp_path, data)?;
+    fs::rename(temp_path, path)
+}
+
+/// Writes the session snapshot, replacing the previous one atomically.
+pub fn save_session(session: &RecordingSession) -> io::Result<()> {
+    let dir = session_dir(&session.id)?;
+    fs::create_dir_all(dir.join("screenshots"))?;
+
+    write_atomic(&dir.join("session.json"), &serde_json::to_vec_pretty(session)?)
+}
+
+fn append_entry(session_id: &str, entry: &JournalEntry) -> io::Result<()> {
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(session_dir(session_id)?.join("steps.jsonl"))?;
+    file.write_all(&line)?;
+    file.sync_data()
+}
+
+/// Appends a stamped step. The screenshot goes to its own file so the
+/// journal stays small enough to scan on startup.
+pub fn append_step(info: &ElementInfo) -> io::Result<()> {
+    let (session_id, sequence) = match (&info.session_id, info.sequence) {
+        (Some(session_id), Some(sequence)) => (session_id, sequence),
+        _ => return Ok(()),
+    };
+
+    let mut step = Box::new(info.clone());
+    let screenshot_file = match step.screenshot.take() {
+        Some(screenshot) => {
+            let bytes = BASE64.decode(screenshot)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let file_name = format!("{}.jpg", sequence);
+            write_synced(&session_dir(session_id)?.join("screenshots").join(&file_name), &bytes)?;
+            Some(file_name)
+        }
+        None => None,
+    };
+
+    append_entry(session_id, &JournalEntry::Step { step, screenshot_file })
+}
+
+pub fn append_discard(session_id: &str, sequence: u64) -> io::Result<()> {
+    append_entry(session_id, &JournalEntry::Discarded { sequence })
+}
+
+/// What the recovery prompt shows for an interrupted session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session: RecordingSession,
+    pub step_count: usize,
+    pub last_step_at: Option<String>,
+}
+
+/// Reads a session back. A torn last line from a crash mid-write is ignored.
+pub fn load_session(session_id: &str) -> io::Result<JournaledSession> {
+    read_session(session_id, true)
+}
+
+fn read_session(session_id: &str, inline_screenshots: bool) -> io::Result<JournaledSession> {
+    let dir = session_dir(session_id)?;
+    let mut session: RecordingSession = serde_json::from_str(&fs::read_to_string(dir.join("session.json"))?)?;
+
+    let mut steps = Vec::new();
+    let mut discarded = Vec::new();
+    if let Ok(file) = File::open(dir.join("steps.jsonl")) {
+        for line in BufReader::new(file).lines() {
+            let entry = match serde_json::from_str::<JournalEntry>(&line?) {
+                Ok(entry) => entry,
+                Err(_) => break,
+            };
+            match entry {
+                JournalEntry::Step { mut step, screenshot_file } => {
+                    step.screenshot = screenshot_file
+                        .filter(|_| inline_screenshots)
+                        .and_then(|name| fs::read(dir.join("screenshots").join(name)).ok())
+                        .map(|bytes| BASE64.encode(bytes));
+                    steps.push(*step);
+                }
+                JournalEntry::Discarded { sequence } => discarded.push(sequence),
+            }
+        }
+    }
+
+    steps.retain(|step| !step.sequence.is_some_and(|sequence| discarded.contains(&sequence)));
+    let last_sequence = steps.iter()
+        .filter_map(|step| step.sequence)
+        .chain(discarded.iter().copied())
+        .max()
+        .unwrap_or(0);
+    session.restore_steps(
+        last_sequence,
+        discarded,
+        steps.iter().filter_map(|step| step.sequence).collect(),
+    );
+
+    Ok(JournaledSession { session, steps })
+}
+
+/// Sessions that never reached `stop_tracking`, newest first. Sessions that
+/// can't be read are skipped.
+pub fn list_interrupted_sessions() -> io::Result<Vec<SessionSummary>> {
+    let entries = match fs::read_dir(sessions_dir()?) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut sessions: Vec<SessionSummary> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| read_session(&entry.file_name().to_string_lossy(), false).ok())
+        .filter(|journaled| journaled.session.stopped_at.is_none())
+        .map(|journaled| SessionSummary {
+            step_count: journaled.steps.len(),
+            last_step_at: journaled.steps.last().map(|step| step.timestamp.clone()),
+            session: journaled.session,
+        })
+        .collect();
+    sessions.sort_by(|a, b| b.session.started_at.cmp(&a.session.started_at));
+    Ok(sessions)
+}
+
+pub fn delete_session(session_id: &str) -> io::Result<()> {
+    fs::remove_dir_all(session_dir(session_id)?)
+}
+
+/// Removes closed sessions past the retention period.
+pub fn prune_closed_sessions() -> io::Result<()> {
+    let entries = match fs::read_dir(sessions_dir()?) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(CLOSED_SESSION_RETENTION_DAYS);
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let stopped_at = fs::read_to_string(entry.path().join("session.json")).ok()
+            .and_then(|content| serde_json::from_str::<RecordingSession>(&content).ok())
+            .and_then(|session| session.stopped_at)
+            .and_then(|stopped_at| chrono::DateTime::parse_from_rfc3339(&stopped_at).ok());
+        if stopped_at.is_some_and(|stopped_at| stopped_at < cutoff) {
+            fs::remove_dir_all(entry.path())?;
+        }
+    }
+    Ok(())
+}
@@ -3,11 +3,14 @@ pub mod ui_automation;
 pub mod screenshot;
 pub mod commands;
 pub mod tracking;
+pub mod journal;
+pub mod logging;
 
 use std::fs;
 use tauri::Manager;
-use models::state::{TrackingState, WindowState};
+use models::state::{TrackingState, WindowState, SettingsState, LaunchState};
 use serde::{Serialize, Deserialize};
+use tracking::pipeline::PipelineMetrics;
 use url::Url;
 use dirs;
 
@@ -42,17 +45,31 @@ impl DeepLinkPayload {
 }
 
 #[tauri::command]
-async fn get_launch_details() -> Result<Option<DeepLinkPayload>, String> {
-    Ok(DeepLinkPayload::load_from_disk())
+async fn get_launch_details(launch_state: tauri::State<'_, LaunchState>) -> Result<Option<DeepLinkPayload>, String> {
+    let payload = DeepLinkPayload::load_from_disk();
+    if let Some(payload) = &payload {
+        launch_state.set(payload.clone());
+    }
+    Ok(payload)
 }
 
 pub fn run() {
+    // Held until exit so buffered log lines reach the file
+    let _log_guard = logging::init();
+
     let tracking_state = TrackingState::default();
     let tracking_state_clone_for_thread = tracking_state.clone();
+    let settings_state = SettingsState::load();
+    let settings_state_clone_for_thread = settings_state.clone();
+    let pipeline_metrics = PipelineMetrics::default();
+    let pipeline_metrics_clone_for_thread = pipeline_metrics.clone();
 
     tauri::Builder::default()
         .manage(tracking_state)
         .manage(WindowState::default())
+        .manage(settings_state)
+        .manage(LaunchState::default())
+        .manage(pipeline_metrics)
         .setup(move |app| {
             // First prepare the deep link plugin
             tauri_plugin_deep_link::prepare("matapass");
@@ -74,11 +91,13 @@ pub fn run() {
                             .unwrap_or_default(),
                     };
 
+                    handle.state::<LaunchState>().set(payload.clone());
+
                     // Save payload asynchronously
                     let payload_clone = payload.clone();
                     std::thread::spawn(move || {
                         if let Err(e) = payload_clone.save_to_disk() {
-                            eprintln!("Failed to save deep link data: {}", e);
+                            tracing::error!(error = %e, "Failed to save deep link data");
                         }
                     });
 
@@ -100,15 +119,23 @@ pub fn run() {
                                 window.show().ok();
                                 window.set_focus().ok();
                             }
-                            Err(e) => eprintln!("Failed to create window: {}", e),
+                            Err(e) => tracing::error!(error = %e, "Failed to create window"),
                         }
                     }
                 }
             })?;
 
+            std::thread::spawn(|| {
+                if let Err(e) = journal::prune_closed_sessions() {
+                    tracing::warn!(error = %e, "Failed to prune old session journals");
+                }
+            });
+
             tracking::loop_handler::setup_tracking_loop(
                 app.handle(),
-                tracking_state_clone_for_thread
+                tracking_state_clone_for_thread,
+                settings_state_clone_for_thread,
+                pipeline_metrics_clone_for_thread
             );
             Ok(())
         })
@@ -117,8 +144,34 @@ pub fn run() {
             commands::tracking::stop_tracking,
             commands::tracking::toggle_pause,
             commands::tracking::enter_compact_mode,
+            commands::tracking::get_pipeline_metrics,
+            commands::tracking::set_step_limit,
+            commands::settings::get_hotkeys,
+            commands::settings::update_hotkeys,
+            commands::settings::reset_hotkeys,
+            commands::settings::get_exclusion_rules,
+            commands::settings::update_exclusion_rules,
+            commands::settings::reset_exclusion_rules,
+            commands::settings::get_idle_settings,
+            commands::settings::update_idle_settings,
+            commands::settings::get_hover_settings,
+            commands::settings::update_hover_settings,
+            commands::settings::get_countdown_settings,
+            commands::settings::update_countdown_settings,
+            commands::settings::get_redaction_settings,
+            commands::settings::update_redaction_settings,
+            commands::settings::get_navigation_settings,
+            commands::settings::update_navigation_settings,
+            commands::scope::list_open_windows,
+            commands::scope::set_target_scope,
+            commands::recovery::list_interrupted_sessions,
+            commands::recovery::resume_interrupted_session,
+            commands::recovery::recover_session_as_guide,
+            commands::recovery::discard_interrupted_session,
             commands::guide::load_guides,
+            commands::guide::locate_step_element,
             commands::debug::debug_deep_link,
+            commands::debug::get_recent_logs,
             get_launch_details,
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,87 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, EnvFilter};
+use dirs;
+
+const LOG_FILE_PREFIX: &str = "matapass";
+const LOG_FILE_SUFFIX: &str = "log";
+// One file per day; older ones are deleted by the appender
+const MAX_LOG_FILES: usize = 7;
+
+// Overrides the default filter, e.g. MATAPASS_LOG=tauriguidecreator_lib::tracking=debug
+const LOG_FILTER_ENV: &str = "MATAPASS_LOG";
+const DEFAULT_LOG_FILTER: &str = "info";
+
+fn logs_dir() -> io::Result<PathBuf> {
+    let dir = dirs::data_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Failed to get app data directory"))?;
+    Ok(dir.join("MataPass").join("logs"))
+}
+
+/// Logs to the console and to daily files under `MataPass/logs`. The
+/// returned guard flushes the file writer and must live until exit.
+pub fn init() -> Option<WorkerGuard> {
+    let filter = || EnvFilter::try_from_env(LOG_FILTER_ENV)
+        .unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+
+    let file_appender = logs_dir().and_then(|dir| {
+        fs::create_dir_all(&dir)?;
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix(LOG_FILE_SUFFIX)
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .map_err(io::Error::other)
+    });
+
+    let console_layer = fmt::layer().with_target(true).with_filter(filter());
+    match file_appender {
+        Ok(file_appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(file_appender);
+            let file_layer = fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false)
+                .with_target(true)
+                .with_filter(filter());
+            tracing_subscriber::registry().with(console_layer).with(file_layer).init();
+            Some(guard)
+        }
+        Err(e) => {
+            tracing_subscriber::registry().with(console_layer).init();
+            tracing::warn!(error = %e, "Log files unavailable, logging to the console only");
+            None
+        }
+    }
+}
+
+/// The last `max_lines` lines across the newest log files, oldest first.
+pub fn recent_lines(max_lines: usize) -> io::Result<Vec<String>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(logs_dir()?)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(LOG_FILE_PREFIX) && name.ends_with(LOG_FILE_SUFFIX)))
+        .collect();
+    // Dated names sort chronologically
+    files.sort();
+
+    let mut lines: Vec<String> = Vec::new();
+    for file in files.iter().rev() {
+        let content = fs::read_to_string(file)?;
+        let mut file_lines: Vec<String> = content.lines().map(str::to_string).collect();
+        file_lines.append(&mut lines);
+        lines = file_lines;
+        if lines.len() >= max_lines {
+            break;
+        }
+    }
+
+    let start = lines.len().saturating_sub(max_lines);
+    Ok(lines.split_off(start))
+}
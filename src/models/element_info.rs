@@ -2,14 +2,132 @@ use serde::{Serialize, Deserialize};
 use std::fmt;
 
 use super::screen::ScreenContext;
+use super::selector::{ElementSelector, SelectorSegment};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ActionCategory {
     Click,
+    DoubleClick,
+    Drag,
+    Scroll,
     Keystroke,
+    TextInput,
+    Shortcut,
+    Hover,
+    FocusChanged,
+    WindowActivated,
     Manual,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScrollDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// A rectangle in pixels; `x`/`y` is the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoundingRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl BoundingRect {
+    pub fn center(&self) -> (i32, i32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+}
+
+/// The application a step happened in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppContext {
+    pub process_id: u32,
+    pub process_name: String,
+    pub executable_path: String,
+    /// From the executable's version resource, e.g. "16.0.17328.20162"
+    pub file_version: Option<String>,
+    /// e.g. "Microsoft Office"
+    pub product_name: Option<String>,
+    /// What Windows shows as the app's name, e.g. "Microsoft Excel"
+    pub file_description: Option<String>,
+    /// Top-level window the element belongs to
+    pub window_handle: isize,
+    pub window_class: String,
+}
+
+/// One level above a step's element, for descriptions like "Click 'Bold'
+/// in the Font group of the Home tab".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AncestorInfo {
+    pub control_type: String,
+    pub name: String,
+    pub automation_id: String,
+}
+
+impl From<&SelectorSegment> for AncestorInfo {
+    fn from(segment: &SelectorSegment) -> Self {
+        Self {
+            control_type: segment.control_type.clone(),
+            name: segment.name.clone(),
+            automation_id: segment.automation_id.clone(),
+        }
+    }
+}
+
+/// Identifying fields of an element that isn't the main subject of a step,
+/// such as the drop target of a drag.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementSummary {
+    pub name: String,
+    pub control_type: String,
+    pub automation_id: String,
+    pub class_name: String,
+    pub window_title: String,
+}
+
+impl From<&ElementInfo> for ElementSummary {
+    fn from(info: &ElementInfo) -> Self {
+        Self {
+            name: info.name.clone(),
+            control_type: info.control_type.clone(),
+            automation_id: info.automation_id.clone(),
+            class_name: info.class_name.clone(),
+            window_title: info.window_title.clone(),
+        }
+    }
+}
+
+/// The step's element is where the drag started.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DragDetails {
+    pub end_x: i32,
+    pub end_y: i32,
+    pub path: Vec<(i32, i32)>,
+    pub drop_target: Option<ElementSummary>,
+}
+
+/// Where a clicked menu item sits, for descriptions like "Right-click the
+/// file, then choose Send to > Compressed folder".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MenuPath {
+    /// The items that opened each menu, then the clicked item
+    pub items: Vec<String>,
+    /// What was right-clicked to open a context menu
+    pub origin: Option<ElementSummary>,
+}
+
+/// The step's element is the container that scrolled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrollDetails {
+    pub direction: ScrollDirection,
+    /// Wheel notches, rounded up
+    pub amount: i32,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ElementInfo {
     pub x: Option<i32>,
@@ -20,7 +138,12 @@ pub struct ElementInfo {
     pub automation_id: String,
     pub class_name: String,
     pub window_title: String,
+    #[serde(default)]
+    pub app: Option<AppContext>,
     pub parent_name: String,
+    /// Parent first, up to and including the top-level window
+    #[serde(default)]
+    pub ancestors: Vec<AncestorInfo>,
     pub action_type: String,
     pub action_category: ActionCategory,
     pub timestamp: String,
@@ -28,6 +151,38 @@ pub struct ElementInfo {
     pub value: String,
     pub state: String,
     pub help_text: String,
+    /// The element is a password field
+    #[serde(default)]
+    pub is_password: bool,
+    /// Something was hidden from this step (its value, part of the
+    /// screenshot) by the redaction settings
+    #[serde(default)]
+    pub redacted: bool,
+    pub key_combo: Option<String>,
+    pub drag: Option<DragDetails>,
+    pub scroll: Option<ScrollDetails>,
+    /// Set for clicks on menu items
+    #[serde(default)]
+    pub menu: Option<MenuPath>,
+    /// The element's bounding box in screen coordinates
+    #[serde(default)]
+    pub bounds: Option<BoundingRect>,
+    /// The same box in screenshot pixels, clipped to the image
+    #[serde(default)]
+    pub screenshot_bounds: Option<BoundingRect>,
+    /// Text of the tooltip shown when a hover step was captured
+    #[serde(default)]
+    pub tooltip: Option<String>,
+    /// Finds this element again when the guide is replayed or checked
+    #[serde(default)]
+    pub selector: Option<ElementSelector>,
+    /// Set when the step is emitted; `None` outside a recording session
+    pub session_id: Option<String>,
+    pub sequence: Option<u64>,
+    /// Outside the session's target scope; only set when the scope tags
+    /// rather than ignores such interactions
+    #[serde(default)]
+    pub out_of_scope: bool,
 }
 
 impl fmt::Debug for ElementInfo {
@@ -41,7 +196,9 @@ impl fmt::Debug for ElementInfo {
             .field("automation_id", &self.automation_id)
             .field("class_name", &self.class_name)
             .field("window_title", &self.window_title)
+            .field("app", &self.app)
             .field("parent_name", &self.parent_name)
+            .field("ancestors", &self.ancestors)
             .field("action_type", &self.action_type)
             .field("action_category", &self.action_category)
             .field("timestamp", &self.timestamp)
@@ -49,6 +206,19 @@ impl fmt::Debug for ElementInfo {
             .field("value", &self.value)
             .field("state", &self.state)
             .field("help_text", &self.help_text)
+            .field("is_password", &self.is_password)
+            .field("redacted", &self.redacted)
+            .field("key_combo", &self.key_combo)
+            .field("drag", &self.drag)
+            .field("scroll", &self.scroll)
+            .field("menu", &self.menu)
+            .field("bounds", &self.bounds)
+            .field("screenshot_bounds", &self.screenshot_bounds)
+            .field("tooltip", &self.tooltip)
+            .field("selector", &self.selector)
+            .field("session_id", &self.session_id)
+            .field("sequence", &self.sequence)
+            .field("out_of_scope", &self.out_of_scope)
             .finish()
     }
 }
\ No newline at end of file
@@ -6,6 +6,8 @@ pub enum Error {
     AutomationError(core::Error),
     EmitError(String),
     ElementError(String),
+    InputError(String),
+    WindowError(String),
 }
 
 impl fmt::Display for Error {
@@ -14,6 +16,8 @@ impl fmt::Display for Error {
             Error::AutomationError(e) => write!(f, "Automation error: {}", e),
             Error::EmitError(e) => write!(f, "Event emission error: {}", e),
             Error::ElementError(e) => write!(f, "Element error: {}", e),
+            Error::InputError(e) => write!(f, "Input error: {}", e),
+            Error::WindowError(e) => write!(f, "Window error: {}", e),
         }
     }
 }
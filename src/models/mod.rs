@@ -3,5 +3,8 @@ pub mod state;
 pub mod error;
 pub mod guide;
 pub mod screen;
+pub mod settings;
+pub mod session;
+pub mod selector;
 
 pub use guide::{Guide, GuideStep};  // Export guide types
\ No newline at end of file
@@ -0,0 +1,22 @@
+use serde::{Serialize, Deserialize};
+
+/// One element on the path from a top-level window down to a step's element.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectorSegment {
+    pub control_type: String,
+    pub automation_id: String,
+    pub name: String,
+    pub class_name: String,
+    /// Position among siblings of the same control type, from 0
+    pub index: usize,
+}
+
+/// Enough to find the same control again in a later run of the app, for
+/// replaying, validating and highlighting guide steps.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElementSelector {
+    /// Executable owning the top-level window, e.g. "notepad.exe"
+    pub process_name: String,
+    /// Top-level window first, the element itself last
+    pub path: Vec<SelectorSegment>,
+}
@@ -0,0 +1,208 @@
+use serde::{Serialize, Deserialize};
+
+use crate::ui_automation::window::WindowContext;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PauseInterval {
+    pub paused_at: String,
+    pub resumed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutOfScopeAction {
+    /// Don't record the interaction at all
+    #[default]
+    Ignore,
+    /// Record it with `out_of_scope` set so the editor can filter it
+    Tag,
+}
+
+/// The application(s) a recording is about. Windows match by process name
+/// (ignoring case) or by handle, including dialogs owned by a listed window.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TargetScope {
+    pub process_names: Vec<String>,
+    pub windows: Vec<isize>,
+    pub out_of_scope: OutOfScopeAction,
+}
+
+impl TargetScope {
+    pub fn is_empty(&self) -> bool {
+        self.process_names.is_empty() && self.windows.is_empty()
+    }
+
+    pub fn contains(&self, window: &WindowContext) -> bool {
+        self.process_names.iter().any(|name| name.eq_ignore_ascii_case(&window.process_name))
+            || self.windows.iter().any(|&hwnd| hwnd == window.hwnd || hwnd == window.owner_hwnd)
+    }
+}
+
+/// One recording, from `start_tracking` to `stop_tracking`. Every emitted
+/// step carries the session id and a sequence number, so a missing or
+/// repeated number means a lost or duplicated event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingSession {
+    pub id: String,
+    pub guide_id: Option<String>,
+    pub started_at: String,
+    pub stopped_at: Option<String>,
+    pub pauses: Vec<PauseInterval>,
+    /// Sequence number of the most recently emitted step, 0 before the first
+    pub last_sequence: u64,
+    /// Sequence numbers removed with the discard hotkey; these are the only
+    /// expected gaps
+    pub discarded: Vec<u64>,
+    /// Recording stops once this many steps are kept
+    #[serde(default)]
+    pub step_limit: Option<u64>,
+    /// Interactions outside this scope are ignored or tagged
+    #[serde(default)]
+    pub scope: Option<TargetScope>,
+    // Steps that can still be discarded, newest last
+    #[serde(skip)]
+    live_steps: Vec<u64>,
+}
+
+/// Payload of `recording-progress`, e.g. step 3 of 10.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepProgress {
+    pub session_id: String,
+    pub recorded: u64,
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+}
+
+/// Payload of `recording-idle`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdleNotice {
+    pub session_id: Option<String>,
+    pub idle_secs: u64,
+    pub resume_requires_hotkey: bool,
+}
+
+/// Payload of `recording-countdown`, sent once per second and with
+/// `remaining_secs` 0 when recording begins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountdownTick {
+    pub session_id: Option<String>,
+    pub remaining_secs: u64,
+}
+
+/// Payload of `step-discarded`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscardedStep {
+    pub session_id: String,
+    pub sequence: u64,
+}
+
+impl RecordingSession {
+    pub fn new(guide_id: Option<String>, step_limit: Option<u64>, scope: Option<TargetScope>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            guide_id,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            stopped_at: None,
+            pauses: Vec::new(),
+            last_sequence: 0,
+            discarded: Vec::new(),
+            step_limit,
+            scope,
+            live_steps: Vec::new(),
+        }
+    }
+
+    pub fn next_sequence(&mut self) -> u64 {
+        self.last_sequence += 1;
+        self.live_steps.push(self.last_sequence);
+        self.last_sequence
+    }
+
+    /// Marks the newest step that hasn't been discarded yet as discarded.
+    pub fn discard_last(&mut self) -> Option<DiscardedStep> {
+        let sequence = self.live_steps.pop()?;
+        self.discarded.push(sequence);
+        Some(DiscardedStep {
+            session_id: self.id.clone(),
+            sequence,
+        })
+    }
+
+    /// Steps emitted and not discarded.
+    pub fn recorded_steps(&self) -> u64 {
+        self.live_steps.len() as u64
+    }
+
+    pub fn limit_reached(&self) -> bool {
+        self.step_limit.is_some_and(|limit| self.recorded_steps() >= limit)
+    }
+
+    pub fn progress(&self) -> StepProgress {
+        let recorded = self.recorded_steps();
+        StepProgress {
+            session_id: self.id.clone(),
+            recorded,
+            limit: self.step_limit,
+            remaining: self.step_limit.map(|limit| limit.saturating_sub(recorded)),
+        }
+    }
+
+    /// Rebuilds the step bookkeeping from a journal, which is more current
+    /// than the saved snapshot.
+    pub fn restore_steps(&mut self, last_sequence: u64, discarded: Vec<u64>, live_steps: Vec<u64>) {
+        self.last_sequence = self.last_sequence.max(last_sequence);
+        self.discarded = discarded;
+        self.live_steps = live_steps;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pauses.last().is_some_and(|pause| pause.resumed_at.is_none())
+    }
+
+    pub fn pause(&mut self) {
+        if !self.is_paused() {
+            self.pauses.push(PauseInterval {
+                paused_at: chrono::Utc::now().to_rfc3339(),
+                resumed_at: None,
+            });
+        }
+    }
+
+    /// Opens a pause that began at `since` (RFC 3339), e.g. the last step
+    /// before a crash. It never starts before the session did or before the
+    /// previous pause ended.
+    pub fn pause_since(&mut self, since: &str) {
+        if self.is_paused() {
+            return;
+        }
+        let previous_end = self.pauses.last().and_then(|pause| pause.resumed_at.as_deref());
+        let paused_at = [Some(since), Some(self.started_at.as_str()), previous_end]
+            .into_iter()
+            .flatten()
+            .filter_map(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+            .max()
+            .map(|time| time.with_timezone(&chrono::Utc).to_rfc3339())
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+        self.pauses.push(PauseInterval { paused_at, resumed_at: None });
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(pause) = self.pauses.last_mut() {
+            if pause.resumed_at.is_none() {
+                pause.resumed_at = Some(chrono::Utc::now().to_rfc3339());
+            }
+        }
+    }
+
+    /// Closes the session, ending an open pause at the same moment.
+    pub fn stop(&mut self) {
+        let now = chrono::Utc::now().to_rfc3339();
+        if let Some(pause) = self.pauses.last_mut() {
+            if pause.resumed_at.is_none() {
+                pause.resumed_at = Some(now.clone());
+            }
+        }
+        self.stopped_at = Some(now);
+    }
+}
@@ -0,0 +1,207 @@
+use std::fs;
+use serde::{Serialize, Deserialize};
+use dirs;
+
+use crate::journal;
+
+/// Key combinations as shown to the user, e.g. "Alt+;" or "Ctrl+Shift+F9".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotkeyConfig {
+    pub capture: String,
+    pub pause_resume: String,
+    pub stop: String,
+    pub discard_last_step: String,
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        Self {
+            capture: "Alt+;".to_string(),
+            pause_resume: "Alt+Shift+P".to_string(),
+            stop: "Alt+Shift+S".to_string(),
+            discard_last_step: "Alt+Shift+Z".to_string(),
+        }
+    }
+}
+
+/// Windows matching any rule are never recorded. Name, path and class
+/// comparisons ignore case; `ExecutablePath` also matches a folder prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "pattern", rename_all = "snake_case")]
+pub enum ExclusionRule {
+    ProcessName(String),
+    ExecutablePath(String),
+    WindowClass(String),
+    TitleRegex(String),
+}
+
+pub fn default_exclusion_rules() -> Vec<ExclusionRule> {
+    let password_managers = [
+        "KeePass.exe",
+        "KeePassXC.exe",
+        "1Password.exe",
+        "Bitwarden.exe",
+        "Dashlane.exe",
+        "LastPass.exe",
+        "NordPass.exe",
+        "Enpass.exe",
+        "RoboForm.exe",
+        "keeperpasswordmanager.exe",
+    ];
+
+    let mut rules: Vec<ExclusionRule> = password_managers.iter()
+        .map(|name| ExclusionRule::ProcessName(name.to_string()))
+        .collect();
+    // Windows Security credential prompts
+    rules.push(ExclusionRule::WindowClass("Credential Dialog Xaml Host".to_string()));
+    rules
+}
+
+/// Pauses recording after a stretch without input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdleConfig {
+    pub enabled: bool,
+    pub timeout_secs: u64,
+    /// Stay paused until the pause/resume hotkey (or button) is used,
+    /// instead of resuming on the next click or key press
+    pub resume_requires_hotkey: bool,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout_secs: 300,
+            resume_requires_hotkey: false,
+        }
+    }
+}
+
+/// Gives the user time to bring the target app to the front before
+/// recording starts or resumes, so that click isn't recorded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CountdownConfig {
+    /// 0 starts immediately
+    pub seconds: u64,
+    /// Input this soon after the countdown ends is ignored
+    pub discard_input_ms: u64,
+}
+
+impl Default for CountdownConfig {
+    fn default() -> Self {
+        Self {
+            seconds: 3,
+            discard_input_ms: 0,
+        }
+    }
+}
+
+/// Records a hover step when the cursor rests on one spot, for guides that
+/// point at tooltips. Off by default since most hovers aren't steps.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HoverConfig {
+    pub enabled: bool,
+    pub dwell_ms: u64,
+}
+
+impl Default for HoverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dwell_ms: 1200,
+        }
+    }
+}
+
+/// Steps for navigation that has no click or key step of its own, e.g. an
+/// app moving focus to a field by itself or another window coming to the
+/// front. Off by default; they suit guides that span several apps.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NavigationConfig {
+    pub focus_changes: bool,
+    pub window_switches: bool,
+}
+
+/// What happens to the value of a password field a step lands on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordRedaction {
+    /// Leave the value empty
+    Drop,
+    /// Replace it with a fixed-length mask, so the step still reads as
+    /// "type your password" without giving away its length
+    Mask,
+}
+
+/// Keeps secrets typed into password fields out of recorded steps.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    pub password_value: PasswordRedaction,
+    /// Blur the field in the step's screenshot as well
+    pub blur_screenshot: bool,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            password_value: PasswordRedaction::Mask,
+            blur_screenshot: true,
+        }
+    }
+}
+
+/// User preferences persisted as settings.json in the app data directory.
+/// Every field has a default so older files keep loading as settings grow.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub hotkeys: HotkeyConfig,
+    pub exclusions: Vec<ExclusionRule>,
+    pub idle: IdleConfig,
+    pub hover: HoverConfig,
+    pub countdown: CountdownConfig,
+    pub redaction: RedactionConfig,
+    pub navigation: NavigationConfig,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            hotkeys: HotkeyConfig::default(),
+            exclusions: default_exclusion_rules(),
+            idle: IdleConfig::default(),
+            hover: HoverConfig::default(),
+            countdown: CountdownConfig::default(),
+            redaction: RedactionConfig::default(),
+            navigation: NavigationConfig::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn save_to_disk(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let app_data_dir = dirs::data_dir()
+            .ok_or("Failed to get app data directory")?
+            .join("MataPass");
+
+        fs::create_dir_all(&app_data_dir)?;
+        let json = serde_json::to_string_pretty(&self)?;
+        // A crash mid-write must not reset every setting to its default
+        journal::write_atomic(&app_data_dir.join("settings.json"), json.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load_from_disk() -> Self {
+        dirs::data_dir()
+            .map(|dir| dir.join("MataPass").join("settings.json"))
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+}
@@ -1,11 +1,18 @@
-use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::atomic::{AtomicU8, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use parking_lot::Mutex;
+use std::time::{Duration, Instant};
+use parking_lot::{Mutex, RwLock};
 use tauri::PhysicalSize;
 
+use super::session::{RecordingSession, TargetScope};
+use super::settings::Settings;
+use crate::DeepLinkPayload;
+
 pub const STATE_STOPPED: u8 = 0;
 pub const STATE_RUNNING: u8 = 1;
 pub const STATE_PAUSED: u8 = 2;
+// Counting down before recording starts or resumes; input is ignored
+pub const STATE_COUNTDOWN: u8 = 3;
 
 pub const COMPACT_WIDTH: u32 = 600;
 pub const COMPACT_HEIGHT: u32 = 900;
@@ -13,12 +20,20 @@ pub const COMPACT_HEIGHT: u32 = 900;
 #[derive(Clone)]
 pub struct TrackingState {
     pub state: Arc<AtomicU8>,
+    pub session: Arc<Mutex<Option<RecordingSession>>>,
+    // Steps captured but not yet emitted by the tracking pipeline
+    in_flight: Arc<AtomicUsize>,
+    // When the state last became running, for discarding early input
+    running_since: Arc<Mutex<Option<Instant>>>,
 }
 
 impl Default for TrackingState {
     fn default() -> Self {
         Self {
-            state: Arc::new(AtomicU8::new(0))
+            state: Arc::new(AtomicU8::new(0)),
+            session: Arc::new(Mutex::new(None)),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            running_since: Arc::new(Mutex::new(None)),
         }
     }
 }
@@ -29,12 +44,84 @@ impl TrackingState {
     }
 
     pub fn set_state(&self, state: u8) {
-        self.state.store(state, Ordering::SeqCst)
+        let previous = self.state.swap(state, Ordering::SeqCst);
+        if state == STATE_RUNNING && previous != STATE_RUNNING {
+            *self.running_since.lock() = Some(Instant::now());
+        }
+    }
+
+    /// Moves from `from` to `to` only if nothing changed the state in the
+    /// meantime, e.g. a stop during a countdown.
+    pub fn transition(&self, from: u8, to: u8) -> bool {
+        let changed = self.state.compare_exchange(from, to, Ordering::SeqCst, Ordering::SeqCst).is_ok();
+        if changed && to == STATE_RUNNING {
+            *self.running_since.lock() = Some(Instant::now());
+        }
+        changed
+    }
+
+    /// How long recording has been running since it last started or
+    /// resumed; `None` when it isn't running.
+    pub fn running_for(&self) -> Option<Duration> {
+        if !self.is_running() {
+            return None;
+        }
+        self.running_since.lock().map(|since| since.elapsed())
     }
 
     pub fn get_state(&self) -> u8 {
         self.state.load(Ordering::SeqCst)
     }
+
+    pub fn step_queued(&self) {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn step_finished(&self) {
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Waits for steps still in the pipeline so they land in the session
+    /// before it closes. Returns false if some were still pending.
+    pub fn wait_for_steps(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while self.in_flight.load(Ordering::SeqCst) > 0 {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        true
+    }
+
+    /// Opens a new session, replacing any that wasn't closed.
+    pub fn start_session(
+        &self,
+        guide_id: Option<String>,
+        step_limit: Option<u64>,
+        scope: Option<TargetScope>,
+    ) -> RecordingSession {
+        let session = RecordingSession::new(guide_id, step_limit, scope);
+        *self.session.lock() = Some(session.clone());
+        session
+    }
+
+    /// Makes a session recovered from the journal current again.
+    pub fn restore_session(&self, session: RecordingSession) {
+        *self.session.lock() = Some(session);
+    }
+
+    /// Closes the current session and returns its final state.
+    pub fn stop_session(&self) -> Option<RecordingSession> {
+        let mut session = self.session.lock().take()?;
+        session.stop();
+        Some(session)
+    }
+
+    /// Runs `f` against the current session, if there is one.
+    pub fn with_session<T>(&self, f: impl FnOnce(&mut RecordingSession) -> T) -> Option<T> {
+        self.session.lock().as_mut().map(f)
+    }
 }
 
 #[derive(Default)]
@@ -50,4 +137,55 @@ impl WindowState {
     pub fn get_size(&self) -> Option<PhysicalSize<u32>> {
         *self.original_size.lock()
     }
+}
+
+/// The most recent deep-link payload, kept so recordings can be tied to the
+/// guide they were launched for.
+#[derive(Default)]
+pub struct LaunchState {
+    pub payload: Mutex<Option<DeepLinkPayload>>,
+}
+
+impl LaunchState {
+    pub fn set(&self, payload: DeepLinkPayload) {
+        *self.payload.lock() = Some(payload);
+    }
+
+    pub fn get(&self) -> Option<DeepLinkPayload> {
+        self.payload.lock().clone()
+    }
+}
+
+/// Settings shared between commands and the tracking thread. The revision
+/// lets the tracker notice changes without re-reading on every event.
+#[derive(Clone)]
+pub struct SettingsState {
+    pub settings: Arc<RwLock<Settings>>,
+    revision: Arc<AtomicU64>,
+}
+
+impl SettingsState {
+    pub fn load() -> Self {
+        Self {
+            settings: Arc::new(RwLock::new(Settings::load_from_disk())),
+            revision: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.read().clone()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::SeqCst)
+    }
+
+    /// Applies `change`, persists the result and notifies the tracker.
+    pub fn update(&self, change: impl FnOnce(&mut Settings)) -> Result<Settings, String> {
+        let mut settings = self.settings.write();
+        change(&mut settings);
+        settings.save_to_disk().map_err(|e| e.to_string())?;
+        self.revision.fetch_add(1, Ordering::SeqCst);
+        Ok(settings.clone())
+    }
 }
\ No newline at end of file
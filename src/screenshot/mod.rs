@@ -2,10 +2,62 @@ use screenshots::Screen;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use std::io::Cursor;
 use image::codecs::jpeg::JpegEncoder;
+use image::{imageops, RgbaImage};
+use crate::models::element_info::BoundingRect;
 
-pub fn capture_screenshot(x: i32, y: i32) -> Option<String> {
+const MIN_BLUR_HEIGHT: u32 = 16;
+
+/// An unencoded capture of one monitor, cheap enough to take on the input
+/// thread. Encoding happens later in `encode_frame`.
+pub struct RawFrame {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+    /// The captured monitor in screen coordinates
+    pub monitor: BoundingRect,
+}
+
+impl RawFrame {
+    /// Maps a rectangle in screen coordinates onto this image, clipped to
+    /// it. The image can be larger than the monitor area on scaled displays.
+    pub fn image_rect(&self, rect: &BoundingRect) -> Option<BoundingRect> {
+        if self.monitor.width <= 0 || self.monitor.height <= 0 {
+            return None;
+        }
+        let scale_x = self.width as f64 / self.monitor.width as f64;
+        let scale_y = self.height as f64 / self.monitor.height as f64;
+        let to_image_x = |x: i32| (((x - self.monitor.x) as f64 * scale_x).round() as i32).clamp(0, self.width as i32);
+        let to_image_y = |y: i32| (((y - self.monitor.y) as f64 * scale_y).round() as i32).clamp(0, self.height as i32);
+
+        let (left, top) = (to_image_x(rect.x), to_image_y(rect.y));
+        let (right, bottom) = (to_image_x(rect.x + rect.width), to_image_y(rect.y + rect.height));
+        if right <= left || bottom <= top {
+            return None;
+        }
+        Some(BoundingRect { x: left, y: top, width: right - left, height: bottom - top })
+    }
+
+    /// Blurs a region given in image pixels (see `image_rect`) beyond
+    /// reading, e.g. a password field.
+    pub fn blur_region(&mut self, region: &BoundingRect) {
+        if region.width <= 0 || region.height <= 0 || self.rgba.len() != self.width as usize * self.height as usize * 4 {
+            return;
+        }
+        let Some(mut image) = RgbaImage::from_raw(self.width, self.height, std::mem::take(&mut self.rgba)) else {
+            return;
+        };
+        let (x, y) = (region.x.max(0) as u32, region.y.max(0) as u32);
+        let area = imageops::crop_imm(&image, x, y, region.width.max(0) as u32, region.height.max(0) as u32).to_image();
+        // Scaled to the field so larger text is just as unreadable
+        let sigma = area.height().max(MIN_BLUR_HEIGHT) as f32 / 2.0;
+        imageops::replace(&mut image, &imageops::blur(&area, sigma), x as i64, y as i64);
+        self.rgba = image.into_raw();
+    }
+}
+
+pub fn capture_frame(x: i32, y: i32) -> Option<RawFrame> {
     let screens = Screen::all().ok()?;
-    
+
     let screen = screens.iter().find(|screen| {
         let display_info = screen.display_info;
         x >= display_info.x as i32
@@ -14,17 +66,35 @@ pub fn capture_screenshot(x: i32, y: i32) -> Option<String> {
             && y < display_info.y as i32 + display_info.height as i32
     })?;
 
+    let display_info = screen.display_info;
     let image = screen.capture().ok()?;
-    
+    Some(RawFrame {
+        width: image.width(),
+        height: image.height(),
+        rgba: image.into_raw(),
+        monitor: BoundingRect {
+            x: display_info.x,
+            y: display_info.y,
+            width: display_info.width as i32,
+            height: display_info.height as i32,
+        },
+    })
+}
+
+pub fn encode_frame(frame: &RawFrame) -> Option<String> {
     let mut buffer = Cursor::new(Vec::new());
     let mut encoder = JpegEncoder::new_with_quality(&mut buffer, 85);
     encoder.encode(
-        image.as_raw(),
-        image.width(),
-        image.height(),
+        &frame.rgba,
+        frame.width,
+        frame.height,
         image::ColorType::Rgba8
     ).ok()?;
-    
+
     let base64_string = BASE64.encode(buffer.get_ref());
     Some(base64_string)
-}
\ No newline at end of file
+}
+
+pub fn capture_screenshot(x: i32, y: i32) -> Option<String> {
+    encode_frame(&capture_frame(x, y)?)
+}
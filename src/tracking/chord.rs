@@ -0,0 +1,74 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::keys::*;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub win: bool,
+}
+
+/// A key pressed together with modifiers, e.g. Ctrl+Shift+N.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub modifiers: Modifiers,
+    pub key_code: i32,
+}
+
+impl Chord {
+    pub fn new(modifiers: Modifiers, key_code: i32) -> Self {
+        Self { modifiers, key_code: normalize_key(key_code) }
+    }
+}
+
+// Modifiers are always written in Ctrl, Alt, Shift, Win order so the same
+// combination renders identically no matter which key went down first.
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = &self.modifiers;
+        for (held, label) in [(m.ctrl, "Ctrl"), (m.alt, "Alt"), (m.shift, "Shift"), (m.win, "Win")] {
+            if held {
+                write!(f, "{}+", label)?;
+            }
+        }
+        match key_display_name(self.key_code) {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "0x{:02X}", self.key_code),
+        }
+    }
+}
+
+/// Parses the `Display` form back, case-insensitively, e.g. "ctrl+shift+n".
+impl FromStr for Chord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        // "+" itself can't be a key name here; the '=' key renders as "="
+        let (key, modifier_names) = parts.split_last()
+            .filter(|(key, _)| !key.is_empty())
+            .ok_or_else(|| format!("Missing key in shortcut '{}'", s))?;
+
+        let mut modifiers = Modifiers::default();
+        for name in modifier_names {
+            let flag = match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut modifiers.ctrl,
+                "alt" => &mut modifiers.alt,
+                "shift" => &mut modifiers.shift,
+                "win" | "windows" | "meta" | "super" => &mut modifiers.win,
+                _ => return Err(format!("Unknown modifier '{}' in shortcut '{}'", name, s)),
+            };
+            *flag = true;
+        }
+
+        let key_code = (0x08..=0xFE)
+            .filter(|&code| !is_modifier(code))
+            .find(|&code| key_display_name(code).is_some_and(|n| n.eq_ignore_ascii_case(key)))
+            .ok_or_else(|| format!("Unknown key '{}' in shortcut '{}'", key, s))?;
+
+        Ok(Chord::new(modifiers, key_code))
+    }
+}
@@ -0,0 +1,64 @@
+use regex::Regex;
+
+use crate::models::settings::ExclusionRule;
+use crate::ui_automation::window::WindowContext;
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    ProcessName(String),
+    ExecutablePath(String),
+    WindowClass(String),
+    TitleRegex(Regex),
+}
+
+/// Compiled form of the user's exclusion rules plus the built-in rule that
+/// skips every window owned by this process, whatever its title.
+#[derive(Debug, Clone)]
+pub struct ExclusionFilter {
+    own_process_id: u32,
+    matchers: Vec<Matcher>,
+}
+
+impl Default for ExclusionFilter {
+    fn default() -> Self {
+        Self {
+            own_process_id: std::process::id(),
+            matchers: Vec::new(),
+        }
+    }
+}
+
+impl ExclusionFilter {
+    /// Fails on the first rule that can't be compiled, naming it.
+    pub fn new(rules: &[ExclusionRule]) -> Result<Self, String> {
+        let matchers = rules.iter()
+            .map(|rule| Ok(match rule {
+                ExclusionRule::ProcessName(name) => Matcher::ProcessName(name.to_lowercase()),
+                ExclusionRule::ExecutablePath(path) => Matcher::ExecutablePath(path.to_lowercase()),
+                ExclusionRule::WindowClass(class) => Matcher::WindowClass(class.to_lowercase()),
+                ExclusionRule::TitleRegex(pattern) => Matcher::TitleRegex(
+                    Regex::new(pattern).map_err(|e| format!("Invalid title pattern '{}': {}", pattern, e))?
+                ),
+            }))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self { matchers, ..Self::default() })
+    }
+
+    /// Returns the reason the window is excluded, if it is.
+    pub fn excluded_by(&self, window: &WindowContext) -> Option<String> {
+        if window.process_id == self.own_process_id {
+            return Some("own process".to_string());
+        }
+
+        self.matchers.iter().find_map(|matcher| {
+            let hit = match matcher {
+                Matcher::ProcessName(name) => window.process_name.to_lowercase() == *name,
+                Matcher::ExecutablePath(path) => window.executable_path.to_lowercase().starts_with(path.as_str()),
+                Matcher::WindowClass(class) => window.window_class.to_lowercase() == *class,
+                Matcher::TitleRegex(regex) => regex.is_match(&window.window_title),
+            };
+            hit.then(|| format!("{:?}", matcher))
+        })
+    }
+}
@@ -0,0 +1,147 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CallNextHookEx, DispatchMessageW, GetMessageW, PostThreadMessageW, SetWindowsHookExW,
+    TranslateMessage, UnhookWindowsHookEx, HC_ACTION, HHOOK, KBDLLHOOKSTRUCT, MSG, MSLLHOOKSTRUCT,
+    WH_KEYBOARD_LL, WH_MOUSE_LL, WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDOWN, WM_LBUTTONUP,
+    WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_QUIT,
+    WM_RBUTTONDOWN, WM_RBUTTONUP, WM_SYSKEYDOWN, WM_SYSKEYUP, WM_XBUTTONDOWN, WM_XBUTTONUP,
+    XBUTTON1,
+};
+
+use crate::models::error::Error;
+use super::input::{InputEvent, InputSource, MouseButton};
+
+// Hook procedures are plain functions, so they reach the active source through this.
+static EVENT_SENDER: Lazy<Mutex<Option<Sender<InputEvent>>>> = Lazy::new(|| Mutex::new(None));
+
+// Which side button an XBUTTON message is for is the high word of mouseData
+fn x_button(mouse_data: u32) -> MouseButton {
+    if (mouse_data >> 16) as u16 == XBUTTON1 {
+        MouseButton::X1
+    } else {
+        MouseButton::X2
+    }
+}
+
+fn forward(event: InputEvent) {
+    if let Some(sender) = EVENT_SENDER.lock().as_ref() {
+        let _ = sender.send(event);
+    }
+}
+
+unsafe extern "system" fn mouse_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code == HC_ACTION as i32 {
+        let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+        let (x, y) = (info.pt.x, info.pt.y);
+        let time = Instant::now();
+        let event = match wparam.0 as u32 {
+            WM_LBUTTONDOWN => Some(InputEvent::MousePress { button: MouseButton::Left, x, y, time }),
+            WM_LBUTTONUP => Some(InputEvent::MouseRelease { button: MouseButton::Left, x, y, time }),
+            WM_RBUTTONDOWN => Some(InputEvent::MousePress { button: MouseButton::Right, x, y, time }),
+            WM_RBUTTONUP => Some(InputEvent::MouseRelease { button: MouseButton::Right, x, y, time }),
+            WM_MBUTTONDOWN => Some(InputEvent::MousePress { button: MouseButton::Middle, x, y, time }),
+            WM_MBUTTONUP => Some(InputEvent::MouseRelease { button: MouseButton::Middle, x, y, time }),
+            WM_XBUTTONDOWN => Some(InputEvent::MousePress { button: x_button(info.mouseData), x, y, time }),
+            WM_XBUTTONUP => Some(InputEvent::MouseRelease { button: x_button(info.mouseData), x, y, time }),
+            WM_MOUSEMOVE => Some(InputEvent::MouseMove { x, y, time }),
+            WM_MOUSEWHEEL | WM_MOUSEHWHEEL => {
+                // The wheel delta is the signed high word of mouseData
+                let delta = (info.mouseData >> 16) as u16 as i16 as i32;
+                let horizontal = wparam.0 as u32 == WM_MOUSEHWHEEL;
+                Some(InputEvent::MouseWheel { delta, horizontal, x, y, time })
+            }
+            _ => None,
+        };
+        if let Some(event) = event {
+            forward(event);
+        }
+    }
+    CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+}
+
+unsafe extern "system" fn keyboard_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code == HC_ACTION as i32 {
+        let info = &*(lparam.0 as *const KBDLLHOOKSTRUCT);
+        let key_code = info.vkCode as i32;
+        let time = Instant::now();
+        match wparam.0 as u32 {
+            WM_KEYDOWN | WM_SYSKEYDOWN => forward(InputEvent::KeyPress { key_code, time }),
+            WM_KEYUP | WM_SYSKEYUP => forward(InputEvent::KeyRelease { key_code, time }),
+            _ => {}
+        }
+    }
+    CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+}
+
+/// Input source backed by WH_MOUSE_LL/WH_KEYBOARD_LL hooks. The hooks live on
+/// a dedicated thread running a message loop; dropping the source ends it.
+pub struct HookInputSource {
+    receiver: Receiver<InputEvent>,
+    hook_thread_id: u32,
+}
+
+impl HookInputSource {
+    pub fn start() -> Result<Self, Error> {
+        let (sender, receiver) = mpsc::channel();
+        *EVENT_SENDER.lock() = Some(sender);
+
+        let (ready_tx, ready_rx) = mpsc::channel();
+        std::thread::spawn(move || unsafe {
+            let module = GetModuleHandleW(PCWSTR::null()).unwrap_or_default();
+            let mouse_hook = match SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook_proc), module, 0) {
+                Ok(hook) => hook,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(Error::from(e)));
+                    return;
+                }
+            };
+            let keyboard_hook = match SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_hook_proc), module, 0) {
+                Ok(hook) => hook,
+                Err(e) => {
+                    UnhookWindowsHookEx(mouse_hook);
+                    let _ = ready_tx.send(Err(Error::from(e)));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(GetCurrentThreadId()));
+
+            // Low-level hooks are only called while this thread pumps messages
+            let mut msg = MSG::default();
+            while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+
+            UnhookWindowsHookEx(keyboard_hook);
+            UnhookWindowsHookEx(mouse_hook);
+        });
+
+        let hook_thread_id = ready_rx
+            .recv()
+            .map_err(|e| Error::InputError(e.to_string()))??;
+
+        Ok(Self { receiver, hook_thread_id })
+    }
+}
+
+impl InputSource for HookInputSource {
+    fn next_event(&mut self, timeout: Duration) -> Option<InputEvent> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+}
+
+impl Drop for HookInputSource {
+    fn drop(&mut self) {
+        *EVENT_SENDER.lock() = None;
+        unsafe {
+            PostThreadMessageW(self.hook_thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::models::settings::HotkeyConfig;
+use super::chord::Chord;
+use super::keys::{VK_F1, VK_F24};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    Capture,
+    PauseResume,
+    Stop,
+    DiscardLastStep,
+}
+
+// Combinations Windows or nearly every app already owns
+const RESERVED_CHORDS: &[&str] = &[
+    "Alt+Tab", "Alt+F4", "Alt+Esc", "Alt+Space", "Ctrl+Esc", "Ctrl+Alt+Delete", "Ctrl+Shift+Esc",
+    "Win+L", "Win+D", "Win+E", "Win+R", "Win+Tab",
+    "Ctrl+A", "Ctrl+C", "Ctrl+V", "Ctrl+X", "Ctrl+Z", "Ctrl+Y", "Ctrl+S",
+];
+
+/// Parsed and validated form of `HotkeyConfig`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotkeyBindings {
+    bindings: Vec<(HotkeyAction, Chord)>,
+}
+
+impl Default for HotkeyBindings {
+    fn default() -> Self {
+        HotkeyBindings::try_from(&HotkeyConfig::default()).expect("default hotkeys are valid")
+    }
+}
+
+impl HotkeyBindings {
+    pub fn action_for(&self, chord: &Chord) -> Option<HotkeyAction> {
+        self.bindings.iter().find(|(_, c)| c == chord).map(|(action, _)| *action)
+    }
+}
+
+impl TryFrom<&HotkeyConfig> for HotkeyBindings {
+    type Error = String;
+
+    fn try_from(config: &HotkeyConfig) -> Result<Self, Self::Error> {
+        let reserved: Vec<Chord> = RESERVED_CHORDS.iter()
+            .filter_map(|s| s.parse().ok())
+            .collect();
+
+        let mut bindings: Vec<(HotkeyAction, Chord)> = Vec::new();
+        for (action, label, value) in [
+            (HotkeyAction::Capture, "Capture", &config.capture),
+            (HotkeyAction::PauseResume, "Pause/resume", &config.pause_resume),
+            (HotkeyAction::Stop, "Stop", &config.stop),
+            (HotkeyAction::DiscardLastStep, "Discard last step", &config.discard_last_step),
+        ] {
+            let chord: Chord = value.parse()?;
+            let m = chord.modifiers;
+            let is_function_key = (VK_F1..=VK_F24).contains(&chord.key_code);
+            // Plain or Shift-only keys would fire while the user types
+            if !(m.ctrl || m.alt || m.win || is_function_key) {
+                return Err(format!("{} hotkey '{}' needs Ctrl, Alt or Win, or a function key", label, chord));
+            }
+            if reserved.contains(&chord) {
+                return Err(format!("{} hotkey '{}' is reserved by Windows or common apps", label, chord));
+            }
+            if bindings.iter().any(|(_, c)| *c == chord) {
+                return Err(format!("{} hotkey '{}' is already assigned to another action", label, chord));
+            }
+            bindings.push((action, chord));
+        }
+
+        Ok(Self { bindings })
+    }
+}
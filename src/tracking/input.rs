@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    /// Side buttons, mapped to Back and Forward by browsers and most IDEs
+    X1,
+    X2,
+}
+
+impl MouseButton {
+    pub fn click_action_type(&self) -> &'static str {
+        match self {
+            MouseButton::Left => "click",
+            MouseButton::Right => "right_click",
+            MouseButton::Middle => "middle_click",
+            MouseButton::X1 => "x1_click",
+            MouseButton::X2 => "x2_click",
+        }
+    }
+
+    pub fn drag_action_type(&self) -> &'static str {
+        match self {
+            MouseButton::Left => "drag",
+            MouseButton::Right => "right_drag",
+            MouseButton::Middle => "middle_drag",
+            MouseButton::X1 => "x1_drag",
+            MouseButton::X2 => "x2_drag",
+        }
+    }
+}
+
+/// A discrete press/release delivered by an `InputSource`. Positions are in
+/// screen coordinates and `time` is when the source observed the event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    MousePress { button: MouseButton, x: i32, y: i32, time: Instant },
+    MouseRelease { button: MouseButton, x: i32, y: i32, time: Instant },
+    MouseMove { x: i32, y: i32, time: Instant },
+    /// `delta` is in wheel units (120 per notch); positive is up/right.
+    MouseWheel { delta: i32, horizontal: bool, x: i32, y: i32, time: Instant },
+    KeyPress { key_code: i32, time: Instant },
+    KeyRelease { key_code: i32, time: Instant },
+}
+
+impl InputEvent {
+    pub fn time(&self) -> Instant {
+        match *self {
+            InputEvent::MousePress { time, .. }
+            | InputEvent::MouseRelease { time, .. }
+            | InputEvent::MouseMove { time, .. }
+            | InputEvent::MouseWheel { time, .. }
+            | InputEvent::KeyPress { time, .. }
+            | InputEvent::KeyRelease { time, .. } => time,
+        }
+    }
+}
+
+pub trait InputSource: Send {
+    /// Waits up to `timeout` for the next event. `None` means nothing arrived
+    /// in time (or the source is exhausted), not that the source failed.
+    fn next_event(&mut self, timeout: Duration) -> Option<InputEvent>;
+}
+
+/// In-memory source that replays a prepared sequence of events. Timestamps
+/// advance only through `wait`, so sequences are deterministic.
+pub struct ScriptedInputSource {
+    events: VecDeque<InputEvent>,
+    clock: Instant,
+}
+
+impl Default for ScriptedInputSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScriptedInputSource {
+    pub fn new() -> Self {
+        Self {
+            events: VecDeque::new(),
+            clock: Instant::now(),
+        }
+    }
+
+    pub fn wait(&mut self, millis: u64) -> &mut Self {
+        self.clock += Duration::from_millis(millis);
+        self
+    }
+
+    pub fn push(&mut self, event: InputEvent) -> &mut Self {
+        self.events.push_back(event);
+        self
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton, x: i32, y: i32) -> &mut Self {
+        let time = self.clock;
+        self.push(InputEvent::MousePress { button, x, y, time })
+    }
+
+    pub fn mouse_up(&mut self, button: MouseButton, x: i32, y: i32) -> &mut Self {
+        let time = self.clock;
+        self.push(InputEvent::MouseRelease { button, x, y, time })
+    }
+
+    pub fn mouse_move(&mut self, x: i32, y: i32) -> &mut Self {
+        let time = self.clock;
+        self.push(InputEvent::MouseMove { x, y, time })
+    }
+
+    pub fn wheel(&mut self, delta: i32, x: i32, y: i32) -> &mut Self {
+        let time = self.clock;
+        self.push(InputEvent::MouseWheel { delta, horizontal: false, x, y, time })
+    }
+
+    pub fn click(&mut self, button: MouseButton, x: i32, y: i32) -> &mut Self {
+        self.mouse_down(button, x, y).wait(20).mouse_up(button, x, y)
+    }
+
+    pub fn key_down(&mut self, key_code: i32) -> &mut Self {
+        let time = self.clock;
+        self.push(InputEvent::KeyPress { key_code, time })
+    }
+
+    pub fn key_up(&mut self, key_code: i32) -> &mut Self {
+        let time = self.clock;
+        self.push(InputEvent::KeyRelease { key_code, time })
+    }
+
+    pub fn tap(&mut self, key_code: i32) -> &mut Self {
+        self.key_down(key_code).wait(20).key_up(key_code)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl InputSource for ScriptedInputSource {
+    fn next_event(&mut self, _timeout: Duration) -> Option<InputEvent> {
+        self.events.pop_front()
+    }
+}
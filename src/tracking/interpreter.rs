@@ -0,0 +1,800 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::models::element_info::ScrollDirection;
+use super::chord::{Chord, Modifiers};
+use super::hotkeys::{HotkeyAction, HotkeyBindings};
+use super::input::{InputEvent, MouseButton};
+use super::keys::*;
+
+const KEYSTROKE_DEBOUNCE_TIME: u128 = 150;
+// Matches the Windows defaults for GetDoubleClickTime / SM_CXDOUBLECLK
+pub const DOUBLE_CLICK_TIME: u128 = 500;
+const DOUBLE_CLICK_DISTANCE: i32 = 4;
+// Movement beyond this while a button is held turns the press into a drag
+const DRAG_THRESHOLD: i32 = 8;
+const DRAG_PATH_STEP: i32 = 10;
+const DRAG_PATH_MAX_POINTS: usize = 200;
+// Wheel events closer together than this are reported as one scroll
+const SCROLL_SETTLE_TIME: u128 = 400;
+const WHEEL_DELTA: i32 = 120;
+// Cursor jitter within this distance still counts as resting in place
+const HOVER_TOLERANCE: i32 = 4;
+
+/// What the tracker should record for a given input event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrackedAction {
+    Click { x: i32, y: i32, button: MouseButton },
+    /// Second left click within the double-click window. The tracker merges
+    /// it with the `Click` reported for the first one.
+    DoubleClick { x: i32, y: i32 },
+    Drag { button: MouseButton, start: (i32, i32), end: (i32, i32), path: Vec<(i32, i32)> },
+    Scroll { x: i32, y: i32, direction: ScrollDirection, amount: i32 },
+    Keystroke { action_type: &'static str },
+    /// The first key of a typing run, while focus is still on the field it
+    /// goes to. The run's `TextInput` follows when it ends.
+    TextStarted,
+    /// Keys typed since the last focus change. `text` is reconstructed from
+    /// key codes and should be replaced by the control's value when available.
+    TextInput { text: String },
+    /// A key pressed while Ctrl, Alt or Win was held, rendered like "Ctrl+Shift+N".
+    Shortcut { combo: String },
+    /// One of the user's configured recorder hotkeys; never recorded as a step.
+    Hotkey(HotkeyAction),
+    /// The cursor rested on one spot for the dwell time. Only reported with
+    /// hover detection enabled, and once per resting place.
+    Hover { x: i32, y: i32 },
+}
+
+#[derive(Debug)]
+struct PendingPress {
+    button: MouseButton,
+    start: (i32, i32),
+    time: Instant,
+    path: Vec<(i32, i32)>,
+    dragging: bool,
+}
+
+#[derive(Debug)]
+struct PendingScroll {
+    x: i32,
+    y: i32,
+    direction: ScrollDirection,
+    delta: i32,
+    last_time: Instant,
+}
+
+#[derive(Debug)]
+struct CursorRest {
+    x: i32,
+    y: i32,
+    since: Instant,
+    // Already reported, or interrupted by a press, key or wheel
+    settled: bool,
+}
+
+/// Turns the raw press/release stream into recordable actions. Holds no
+/// Windows or Tauri state so the decision logic can run anywhere.
+#[derive(Debug)]
+pub struct InputInterpreter {
+    pressed_keys: HashSet<i32>,
+    pressed_button: Option<PendingPress>,
+    last_click: Option<(MouseButton, (i32, i32), Instant)>,
+    last_keystroke_time: Option<Instant>,
+    cursor: (i32, i32),
+    typed_text: Option<String>,
+    scroll: Option<PendingScroll>,
+    hotkeys: HotkeyBindings,
+    hover_dwell: Option<Duration>,
+    rest: Option<CursorRest>,
+}
+
+impl Default for InputInterpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputInterpreter {
+    pub fn new() -> Self {
+        Self {
+            pressed_keys: HashSet::new(),
+            pressed_button: None,
+            last_click: None,
+            last_keystroke_time: None,
+            cursor: (0, 0),
+            typed_text: None,
+            scroll: None,
+            hotkeys: HotkeyBindings::default(),
+            hover_dwell: None,
+            rest: None,
+        }
+    }
+
+    /// Last known cursor position, used as a fallback when no element has focus.
+    pub fn cursor_position(&self) -> (i32, i32) {
+        self.cursor
+    }
+
+    pub fn is_key_down(&self, key_code: i32) -> bool {
+        self.pressed_keys.contains(&normalize_key(key_code))
+    }
+
+    pub fn set_hotkeys(&mut self, hotkeys: HotkeyBindings) {
+        self.hotkeys = hotkeys;
+    }
+
+    /// How long the cursor must rest before a `Hover` is reported; `None`
+    /// turns hover detection off.
+    pub fn set_hover_dwell(&mut self, dwell: Option<Duration>) {
+        self.hover_dwell = dwell;
+    }
+
+    /// Drops any typing collected so far, e.g. keys pressed while paused.
+    pub fn discard_text(&mut self) {
+        self.typed_text = None;
+    }
+
+    /// Forgets every gesture in progress (typing, a press or drag, a scroll,
+    /// the last click, the current resting place) without reporting it.
+    /// Held keys stay, so modifiers remain in sync.
+    pub fn reset(&mut self) {
+        self.typed_text = None;
+        self.pressed_button = None;
+        self.scroll = None;
+        self.last_click = None;
+        if let Some(rest) = self.rest.as_mut() {
+            rest.settled = true;
+        }
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        Modifiers {
+            ctrl: self.is_key_down(VK_CONTROL),
+            alt: self.is_key_down(VK_MENU),
+            shift: self.is_key_down(VK_SHIFT),
+            win: self.is_key_down(VK_LWIN) || self.is_key_down(VK_RWIN),
+        }
+    }
+
+    /// Reports anything that is complete only because time has passed, such
+    /// as a scroll burst that stopped. Call when no event arrived for a while.
+    pub fn tick(&mut self, now: Instant) -> Vec<TrackedAction> {
+        let mut actions = Vec::new();
+        let settled = self.scroll.as_ref()
+            .is_some_and(|s| now.saturating_duration_since(s.last_time).as_millis() > SCROLL_SETTLE_TIME);
+        if settled {
+            self.flush_scroll(&mut actions);
+        }
+        self.check_hover(now, &mut actions);
+        actions
+    }
+
+    /// Completes whatever is still being accumulated (typed text, a scroll),
+    /// e.g. before recording pauses.
+    pub fn flush(&mut self) -> Vec<TrackedAction> {
+        let mut actions = Vec::new();
+        self.flush_scroll(&mut actions);
+        self.flush_text(&mut actions);
+        actions
+    }
+
+    pub fn handle_event(&mut self, event: InputEvent) -> Vec<TrackedAction> {
+        let mut actions = Vec::new();
+        if !matches!(event, InputEvent::MouseWheel { .. } | InputEvent::MouseMove { .. }) {
+            self.flush_scroll(&mut actions);
+        }
+        self.track_rest(&event);
+
+        match event {
+            InputEvent::MousePress { button, x, y, time } => {
+                self.cursor = (x, y);
+                // A click moves focus, so whatever was typed before is complete
+                self.flush_text(&mut actions);
+                self.pressed_button = Some(PendingPress {
+                    button,
+                    start: (x, y),
+                    time,
+                    path: vec![(x, y)],
+                    dragging: false,
+                });
+            }
+            InputEvent::MouseMove { x, y, .. } => {
+                self.cursor = (x, y);
+                if let Some(press) = self.pressed_button.as_mut() {
+                    Self::track_drag(press, x, y);
+                }
+            }
+            InputEvent::MouseRelease { button, x, y, .. } => {
+                self.cursor = (x, y);
+                match self.pressed_button.take() {
+                    Some(mut press) if press.button == button => {
+                        Self::track_drag(&mut press, x, y);
+                        self.handle_release(press, (x, y), &mut actions);
+                    }
+                    // Release without a matching press, e.g. held when recording began
+                    other => self.pressed_button = other,
+                }
+            }
+            InputEvent::MouseWheel { delta, horizontal, x, y, time } => {
+                self.cursor = (x, y);
+                self.handle_wheel(delta, horizontal, (x, y), time, &mut actions);
+            }
+            InputEvent::KeyPress { key_code, time } => {
+                let key_code = normalize_key(key_code);
+                // Held keys repeat KeyPress without a release in between
+                if self.pressed_keys.insert(key_code) {
+                    self.handle_key_press(key_code, time, &mut actions);
+                } else if self.typed_text.is_some() {
+                    // ...but held keys keep typing inside a text run
+                    self.handle_typing(key_code);
+                }
+            }
+            InputEvent::KeyRelease { key_code, .. } => {
+                self.pressed_keys.remove(&normalize_key(key_code));
+            }
+        }
+        actions
+    }
+
+    fn track_drag(press: &mut PendingPress, x: i32, y: i32) {
+        let (sx, sy) = press.start;
+        if !press.dragging && ((x - sx).abs() > DRAG_THRESHOLD || (y - sy).abs() > DRAG_THRESHOLD) {
+            press.dragging = true;
+        }
+        if let Some(&(lx, ly)) = press.path.last() {
+            let far_enough = (x - lx).abs() >= DRAG_PATH_STEP || (y - ly).abs() >= DRAG_PATH_STEP;
+            if far_enough && press.path.len() < DRAG_PATH_MAX_POINTS {
+                press.path.push((x, y));
+            }
+        }
+    }
+
+    fn handle_release(&mut self, mut press: PendingPress, end: (i32, i32), actions: &mut Vec<TrackedAction>) {
+        if press.dragging {
+            self.last_click = None;
+            if press.path.last() != Some(&end) {
+                press.path.push(end);
+            }
+            actions.push(TrackedAction::Drag {
+                button: press.button,
+                start: press.start,
+                end,
+                path: press.path,
+            });
+            return;
+        }
+
+        let (x, y) = press.start;
+        let is_double = press.button == MouseButton::Left
+            && self.last_click.is_some_and(|(button, (lx, ly), last_time)| {
+                button == MouseButton::Left
+                    && press.time.saturating_duration_since(last_time).as_millis() <= DOUBLE_CLICK_TIME
+                    && (x - lx).abs() <= DOUBLE_CLICK_DISTANCE
+                    && (y - ly).abs() <= DOUBLE_CLICK_DISTANCE
+            });
+
+        if is_double {
+            // A third click starts over rather than reporting another double-click
+            self.last_click = None;
+            actions.push(TrackedAction::DoubleClick { x, y });
+        } else {
+            self.last_click = Some((press.button, (x, y), press.time));
+            actions.push(TrackedAction::Click { x, y, button: press.button });
+        }
+    }
+
+    fn handle_wheel(&mut self, delta: i32, horizontal: bool, (x, y): (i32, i32), time: Instant, actions: &mut Vec<TrackedAction>) {
+        if delta == 0 {
+            return;
+        }
+        let direction = match (horizontal, delta > 0) {
+            (false, true) => ScrollDirection::Up,
+            (false, false) => ScrollDirection::Down,
+            (true, true) => ScrollDirection::Right,
+            (true, false) => ScrollDirection::Left,
+        };
+
+        if let Some(scroll) = self.scroll.as_mut() {
+            if scroll.direction == direction {
+                scroll.delta += delta.abs();
+                scroll.last_time = time;
+                return;
+            }
+        }
+        self.flush_scroll(actions);
+        self.scroll = Some(PendingScroll { x, y, direction, delta: delta.abs(), last_time: time });
+    }
+
+    fn flush_scroll(&mut self, actions: &mut Vec<TrackedAction>) {
+        if let Some(scroll) = self.scroll.take() {
+            // Precision touchpads send fractions of a notch; round up so any scroll counts
+            let amount = (scroll.delta + WHEEL_DELTA - 1) / WHEEL_DELTA;
+            actions.push(TrackedAction::Scroll {
+                x: scroll.x,
+                y: scroll.y,
+                direction: scroll.direction,
+                amount,
+            });
+        }
+    }
+
+    fn track_rest(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::MouseMove { x, y, time } => {
+                let moved = self.rest.as_ref()
+                    .is_none_or(|rest| (x - rest.x).abs() > HOVER_TOLERANCE || (y - rest.y).abs() > HOVER_TOLERANCE);
+                if moved {
+                    self.rest = Some(CursorRest { x, y, since: time, settled: false });
+                }
+            }
+            // Pointing at something to use it isn't hovering; wait for the next move
+            _ => {
+                if let Some(rest) = self.rest.as_mut() {
+                    rest.settled = true;
+                }
+            }
+        }
+    }
+
+    fn check_hover(&mut self, now: Instant, actions: &mut Vec<TrackedAction>) {
+        let dwell = match self.hover_dwell {
+            Some(dwell) => dwell,
+            None => return,
+        };
+        if self.pressed_button.is_some() {
+            return;
+        }
+        if let Some(rest) = self.rest.as_mut() {
+            if !rest.settled && now.saturating_duration_since(rest.since) >= dwell {
+                rest.settled = true;
+                actions.push(TrackedAction::Hover { x: rest.x, y: rest.y });
+            }
+        }
+    }
+
+    fn handle_key_press(&mut self, key_code: i32, time: Instant, actions: &mut Vec<TrackedAction>) {
+        // Modifiers only matter in combination with the next key
+        if is_modifier(key_code) {
+            return;
+        }
+
+        // Hotkeys skip the debounce: a stop or pause right after another
+        // key must still get through. Held keys don't repeat them, since
+        // only the first press of a key gets here.
+        let chord = Chord::new(self.modifiers(), key_code);
+        if let Some(hotkey) = self.hotkeys.action_for(&chord) {
+            self.flush_text(actions);
+            actions.push(TrackedAction::Hotkey(hotkey));
+            return;
+        }
+
+        let starts_run = self.typed_text.is_none();
+        if self.handle_typing(key_code) {
+            if starts_run {
+                actions.push(TrackedAction::TextStarted);
+            }
+            return;
+        }
+        self.flush_text(actions);
+
+        if Self::within(self.last_keystroke_time, time, KEYSTROKE_DEBOUNCE_TIME) {
+            return;
+        }
+
+        let modifiers = chord.modifiers;
+        let is_chord = modifiers.ctrl || modifiers.alt || modifiers.win
+            || (modifiers.shift && (VK_F1..=VK_F24).contains(&key_code));
+        if is_chord && key_display_name(key_code).is_some() {
+            self.last_keystroke_time = Some(time);
+            actions.push(TrackedAction::Shortcut { combo: chord.to_string() });
+            return;
+        }
+
+        let action_type = match key_code {
+            VK_TAB => "tab",
+            VK_RETURN => "enter",
+            VK_SPACE => "space",
+            VK_ESCAPE => "escape",
+            VK_LEFT => "arrow_left",
+            VK_UP => "arrow_up",
+            VK_RIGHT => "arrow_right",
+            VK_DOWN => "arrow_down",
+            _ => return,
+        };
+
+        self.last_keystroke_time = Some(time);
+        actions.push(TrackedAction::Keystroke { action_type });
+    }
+
+    /// Returns true if the key was consumed as part of a typing run.
+    fn handle_typing(&mut self, key_code: i32) -> bool {
+        let modifiers = self.modifiers();
+        if modifiers.ctrl || modifiers.alt || modifiers.win {
+            return false;
+        }
+
+        let shift = modifiers.shift;
+        match (typed_char(key_code, shift), self.typed_text.as_mut()) {
+            // A lone space outside a text run is more likely toggling a control
+            (Some(' '), None) => false,
+            (Some(ch), Some(text)) => {
+                text.push(ch);
+                true
+            }
+            (Some(ch), None) => {
+                self.typed_text = Some(ch.to_string());
+                true
+            }
+            (None, Some(text)) => match key_code {
+                VK_BACK => {
+                    text.pop();
+                    true
+                }
+                // Caret movement inside the field is part of the edit
+                VK_DELETE | VK_LEFT | VK_RIGHT | VK_HOME | VK_END => true,
+                _ => false,
+            },
+            (None, None) => {
+                if key_code != VK_BACK && key_code != VK_DELETE {
+                    return false;
+                }
+                // Editing an existing value; the control will report the result
+                self.typed_text = Some(String::new());
+                true
+            }
+        }
+    }
+
+    fn flush_text(&mut self, actions: &mut Vec<TrackedAction>) {
+        if let Some(text) = self.typed_text.take() {
+            actions.push(TrackedAction::TextInput { text });
+        }
+    }
+
+    fn within(last: Option<Instant>, now: Instant, window_ms: u128) -> bool {
+        last.is_some_and(|last| now.saturating_duration_since(last).as_millis() <= window_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracking::input::{InputSource, ScriptedInputSource};
+
+    /// Feeds everything queued in `source` to `interpreter`.
+    fn interpret(interpreter: &mut InputInterpreter, source: &mut ScriptedInputSource) -> Vec<TrackedAction> {
+        let mut actions = Vec::new();
+        while let Some(event) = source.next_event(Duration::ZERO) {
+            actions.extend(interpreter.handle_event(event));
+        }
+        actions
+    }
+
+    /// Taps the keys for lowercase letters and spaces.
+    fn type_text(source: &mut ScriptedInputSource, text: &str) {
+        for ch in text.chars() {
+            source.tap(ch.to_ascii_uppercase() as i32).wait(40);
+        }
+    }
+
+    fn text_input(text: &str) -> TrackedAction {
+        TrackedAction::TextInput { text: text.to_string() }
+    }
+
+    // Well past SCROLL_SETTLE_TIME for anything scripted so far
+    fn later() -> Instant {
+        Instant::now() + Duration::from_secs(60)
+    }
+
+    #[test]
+    fn typing_run_ends_at_a_non_typing_key() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        type_text(&mut source, "hello world");
+        source.tap(VK_TAB);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::TextStarted,
+            text_input("hello world"),
+            TrackedAction::Keystroke { action_type: "tab" },
+        ]);
+    }
+
+    #[test]
+    fn typing_run_follows_backspace_and_shift() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        source.key_down(VK_LSHIFT).tap('H' as i32).key_up(VK_LSHIFT).wait(40);
+        type_text(&mut source, "elp");
+        source.tap(VK_BACK).wait(40);
+        type_text(&mut source, "lo");
+        source.tap(VK_RETURN);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::TextStarted,
+            text_input("Hello"),
+            TrackedAction::Keystroke { action_type: "enter" },
+        ]);
+    }
+
+    #[test]
+    fn click_ends_typing_run() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        type_text(&mut source, "abc");
+        source.click(MouseButton::Left, 300, 200);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::TextStarted,
+            text_input("abc"),
+            TrackedAction::Click { x: 300, y: 200, button: MouseButton::Left },
+        ]);
+    }
+
+    #[test]
+    fn lone_space_is_a_keystroke() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        source.tap(VK_SPACE);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Keystroke { action_type: "space" },
+        ]);
+    }
+
+    #[test]
+    fn quick_second_click_is_a_double_click() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        source.click(MouseButton::Left, 100, 100).wait(150).click(MouseButton::Left, 102, 101);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Click { x: 100, y: 100, button: MouseButton::Left },
+            TrackedAction::DoubleClick { x: 102, y: 101 },
+        ]);
+    }
+
+    #[test]
+    fn slow_or_distant_clicks_stay_single() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        source.click(MouseButton::Left, 100, 100)
+            .wait(DOUBLE_CLICK_TIME as u64 + 100)
+            .click(MouseButton::Left, 100, 100)
+            .wait(100)
+            .click(MouseButton::Left, 140, 100);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Click { x: 100, y: 100, button: MouseButton::Left },
+            TrackedAction::Click { x: 100, y: 100, button: MouseButton::Left },
+            TrackedAction::Click { x: 140, y: 100, button: MouseButton::Left },
+        ]);
+    }
+
+    #[test]
+    fn third_click_starts_over() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        for _ in 0..3 {
+            source.click(MouseButton::Left, 50, 50).wait(100);
+        }
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Click { x: 50, y: 50, button: MouseButton::Left },
+            TrackedAction::DoubleClick { x: 50, y: 50 },
+            TrackedAction::Click { x: 50, y: 50, button: MouseButton::Left },
+        ]);
+    }
+
+    #[test]
+    fn right_clicks_never_double() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        source.click(MouseButton::Right, 10, 10).wait(100).click(MouseButton::Right, 10, 10);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Click { x: 10, y: 10, button: MouseButton::Right },
+            TrackedAction::Click { x: 10, y: 10, button: MouseButton::Right },
+        ]);
+    }
+
+    #[test]
+    fn movement_past_threshold_is_a_drag() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        source.mouse_down(MouseButton::Left, 100, 100);
+        for step in 1..=5 {
+            source.wait(20).mouse_move(100 + step * 20, 100 + step * 10);
+        }
+        source.wait(20).mouse_up(MouseButton::Left, 205, 150);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Drag {
+                button: MouseButton::Left,
+                start: (100, 100),
+                end: (205, 150),
+                path: vec![(100, 100), (120, 110), (140, 120), (160, 130), (180, 140), (200, 150), (205, 150)],
+            },
+        ]);
+    }
+
+    #[test]
+    fn reset_drops_gestures_in_progress() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        source.click(MouseButton::Left, 50, 50).wait(100).mouse_down(MouseButton::Left, 100, 100).wait(20).mouse_move(200, 150);
+        source.wait(20).wheel(-WHEEL_DELTA, 400, 300);
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Click { x: 50, y: 50, button: MouseButton::Left },
+        ]);
+
+        interpreter.reset();
+        source.wait(20).mouse_up(MouseButton::Left, 200, 150).wait(100).click(MouseButton::Left, 50, 50);
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Click { x: 50, y: 50, button: MouseButton::Left },
+        ]);
+        assert_eq!(interpreter.tick(later()), vec![]);
+    }
+
+    #[test]
+    fn jitter_while_pressed_is_still_a_click() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        source.mouse_down(MouseButton::Left, 100, 100)
+            .wait(20)
+            .mouse_move(103, 98)
+            .wait(20)
+            .mouse_up(MouseButton::Left, 103, 98);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Click { x: 100, y: 100, button: MouseButton::Left },
+        ]);
+    }
+
+    #[test]
+    fn wheel_burst_is_one_scroll_once_settled() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        for _ in 0..3 {
+            source.wheel(-WHEEL_DELTA, 400, 300).wait(50);
+        }
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![]);
+        assert_eq!(interpreter.tick(Instant::now()), vec![]);
+        assert_eq!(interpreter.tick(later()), vec![
+            TrackedAction::Scroll { x: 400, y: 300, direction: ScrollDirection::Down, amount: 3 },
+        ]);
+    }
+
+    #[test]
+    fn wheel_direction_change_splits_scrolls() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        source.wheel(-WHEEL_DELTA, 400, 300).wait(50).wheel(WHEEL_DELTA / 4, 400, 300);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Scroll { x: 400, y: 300, direction: ScrollDirection::Down, amount: 1 },
+        ]);
+        // Touchpad fractions of a notch still count as one
+        assert_eq!(interpreter.tick(later()), vec![
+            TrackedAction::Scroll { x: 400, y: 300, direction: ScrollDirection::Up, amount: 1 },
+        ]);
+    }
+
+    #[test]
+    fn key_press_ends_scroll() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        source.wheel(WHEEL_DELTA, 400, 300).wait(50).tap(VK_ESCAPE);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Scroll { x: 400, y: 300, direction: ScrollDirection::Up, amount: 1 },
+            TrackedAction::Keystroke { action_type: "escape" },
+        ]);
+    }
+
+    #[test]
+    fn configured_chord_is_a_hotkey() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        // Alt+Shift+P by default
+        source.key_down(VK_LMENU).key_down(VK_LSHIFT).tap('P' as i32).key_up(VK_LSHIFT).key_up(VK_LMENU);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Hotkey(HotkeyAction::PauseResume),
+        ]);
+    }
+
+    #[test]
+    fn other_chords_are_shortcuts() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        source.key_down(VK_RCONTROL).key_down(VK_SHIFT).tap('N' as i32).key_up(VK_SHIFT).key_up(VK_RCONTROL);
+        source.wait(200).key_down(VK_LSHIFT).tap(VK_F1 + 4).key_up(VK_LSHIFT);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Shortcut { combo: "Ctrl+Shift+N".to_string() },
+            TrackedAction::Shortcut { combo: "Shift+F5".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn rebound_hotkeys_replace_defaults() {
+        let mut interpreter = InputInterpreter::new();
+        let config = crate::models::settings::HotkeyConfig {
+            capture: "F9".to_string(),
+            ..Default::default()
+        };
+        interpreter.set_hotkeys(HotkeyBindings::try_from(&config).unwrap());
+        let mut source = ScriptedInputSource::new();
+        source.tap(VK_F1 + 8).wait(200).key_down(VK_MENU).tap(VK_SEMICOLON).key_up(VK_MENU);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Hotkey(HotkeyAction::Capture),
+            TrackedAction::Shortcut { combo: "Alt+;".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn hotkey_right_after_a_keystroke_still_fires() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        source.key_down(VK_TAB).wait(30).key_up(VK_TAB).wait(30);
+        source.key_down(VK_MENU).key_down(VK_SHIFT).key_down('S' as i32);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::Keystroke { action_type: "tab" },
+            TrackedAction::Hotkey(HotkeyAction::Stop),
+        ]);
+    }
+
+    #[test]
+    fn hotkey_ends_typing_run() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        type_text(&mut source, "draft");
+        source.key_down(VK_MENU).key_down(VK_SHIFT).tap('S' as i32);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::TextStarted,
+            text_input("draft"),
+            TrackedAction::Hotkey(HotkeyAction::Stop),
+        ]);
+    }
+
+    #[test]
+    fn flush_completes_text_and_scroll() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        type_text(&mut source, "abc");
+        source.wheel(-WHEEL_DELTA * 2, 400, 300);
+
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![TrackedAction::TextStarted]);
+        assert_eq!(interpreter.flush(), vec![
+            TrackedAction::Scroll { x: 400, y: 300, direction: ScrollDirection::Down, amount: 2 },
+            text_input("abc"),
+        ]);
+        assert_eq!(interpreter.flush(), vec![]);
+    }
+
+    #[test]
+    fn discarded_text_is_not_flushed() {
+        let mut interpreter = InputInterpreter::new();
+        let mut source = ScriptedInputSource::new();
+        type_text(&mut source, "secret");
+        interpret(&mut interpreter, &mut source);
+
+        interpreter.discard_text();
+        assert_eq!(interpreter.flush(), vec![]);
+
+        // The next key starts a new run
+        type_text(&mut source, "x");
+        source.tap(VK_TAB);
+        assert_eq!(interpret(&mut interpreter, &mut source), vec![
+            TrackedAction::TextStarted,
+            text_input("x"),
+            TrackedAction::Keystroke { action_type: "tab" },
+        ]);
+    }
+}
@@ -0,0 +1,103 @@
+// Virtual Key Codes
+pub const VK_BACK: i32 = 0x08;
+pub const VK_TAB: i32 = 0x09;
+pub const VK_RETURN: i32 = 0x0D;
+pub const VK_SHIFT: i32 = 0x10;
+pub const VK_CONTROL: i32 = 0x11;
+pub const VK_MENU: i32 = 0x12;   // Virtual key code for Alt
+pub const VK_ESCAPE: i32 = 0x1B;
+pub const VK_SPACE: i32 = 0x20;
+pub const VK_PRIOR: i32 = 0x21;
+pub const VK_NEXT: i32 = 0x22;
+pub const VK_END: i32 = 0x23;
+pub const VK_HOME: i32 = 0x24;
+pub const VK_LEFT: i32 = 0x25;
+pub const VK_UP: i32 = 0x26;
+pub const VK_RIGHT: i32 = 0x27;
+pub const VK_DOWN: i32 = 0x28;
+pub const VK_SNAPSHOT: i32 = 0x2C;
+pub const VK_INSERT: i32 = 0x2D;
+pub const VK_DELETE: i32 = 0x2E;
+pub const VK_LWIN: i32 = 0x5B;
+pub const VK_RWIN: i32 = 0x5C;
+pub const VK_NUMPAD0: i32 = 0x60;
+pub const VK_NUMPAD9: i32 = 0x69;
+pub const VK_F1: i32 = 0x70;
+pub const VK_F24: i32 = 0x87;
+pub const VK_LSHIFT: i32 = 0xA0;
+pub const VK_RSHIFT: i32 = 0xA1;
+pub const VK_LCONTROL: i32 = 0xA2;
+pub const VK_RCONTROL: i32 = 0xA3;
+pub const VK_LMENU: i32 = 0xA4;
+pub const VK_RMENU: i32 = 0xA5;
+pub const VK_SEMICOLON: i32 = 0xBA; // Virtual key code for ';'
+
+/// Low-level hooks report left/right variants of the modifier keys; fold
+/// them into the generic codes so state checks don't care which side was used.
+pub fn normalize_key(key_code: i32) -> i32 {
+    match key_code {
+        VK_LSHIFT | VK_RSHIFT => VK_SHIFT,
+        VK_LCONTROL | VK_RCONTROL => VK_CONTROL,
+        VK_LMENU | VK_RMENU => VK_MENU,
+        other => other,
+    }
+}
+
+pub fn is_modifier(key_code: i32) -> bool {
+    matches!(normalize_key(key_code), VK_SHIFT | VK_CONTROL | VK_MENU | VK_LWIN | VK_RWIN)
+}
+
+/// Human-readable key name as shown in shortcut combos.
+pub fn key_display_name(key_code: i32) -> Option<String> {
+    let name = match key_code {
+        0x41..=0x5A | 0x30..=0x39 => return Some((key_code as u8 as char).to_string()),
+        VK_NUMPAD0..=VK_NUMPAD9 => return Some(format!("Num {}", key_code - VK_NUMPAD0)),
+        VK_F1..=VK_F24 => return Some(format!("F{}", key_code - VK_F1 + 1)),
+        VK_BACK => "Backspace",
+        VK_TAB => "Tab",
+        VK_RETURN => "Enter",
+        VK_ESCAPE => "Esc",
+        VK_SPACE => "Space",
+        VK_PRIOR => "PageUp",
+        VK_NEXT => "PageDown",
+        VK_END => "End",
+        VK_HOME => "Home",
+        VK_LEFT => "Left",
+        VK_UP => "Up",
+        VK_RIGHT => "Right",
+        VK_DOWN => "Down",
+        VK_SNAPSHOT => "PrintScreen",
+        VK_INSERT => "Insert",
+        VK_DELETE => "Delete",
+        _ => return typed_char(key_code, false).map(|ch| ch.to_string()),
+    };
+    Some(name.to_string())
+}
+
+/// Best-effort character for a key on a US layout. Only used when the
+/// focused control doesn't expose its value, so exactness isn't required.
+pub fn typed_char(key_code: i32, shift: bool) -> Option<char> {
+    let ch = match key_code {
+        0x41..=0x5A => {
+            let ch = key_code as u8 as char;
+            return Some(if shift { ch } else { ch.to_ascii_lowercase() });
+        }
+        0x30..=0x39 if !shift => key_code as u8 as char,
+        0x30..=0x39 => b")!@#$%^&*("[(key_code - 0x30) as usize] as char,
+        VK_NUMPAD0..=VK_NUMPAD9 => (b'0' + (key_code - VK_NUMPAD0) as u8) as char,
+        VK_SPACE => ' ',
+        VK_SEMICOLON => if shift { ':' } else { ';' },
+        0xBB => if shift { '+' } else { '=' },
+        0xBC => if shift { '<' } else { ',' },
+        0xBD => if shift { '_' } else { '-' },
+        0xBE => if shift { '>' } else { '.' },
+        0xBF => if shift { '?' } else { '/' },
+        0xC0 => if shift { '~' } else { '`' },
+        0xDB => if shift { '{' } else { '[' },
+        0xDC => if shift { '|' } else { '\\' },
+        0xDD => if shift { '}' } else { ']' },
+        0xDE => if shift { '"' } else { '\'' },
+        _ => return None,
+    };
+    Some(ch)
+}
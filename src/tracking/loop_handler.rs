@@ -1,310 +1,648 @@
-use std::time::Instant;
-use std::collections::HashMap;
-use tauri::{AppHandle, Manager};
-use windows::Win32::UI::Input::KeyboardAndMouse::GetAsyncKeyState;
-use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Window};
 use windows::Win32::Foundation::POINT;
-use windows::Win32::UI::Accessibility::{IUIAutomation, UIA_BoundingRectanglePropertyId};
+use windows::Win32::System::Com::{CoInitializeEx, COINIT_MULTITHREADED};
 
 use crate::models::error::Error;
-use crate::models::element_info::{ElementInfo, ActionCategory};
-use crate::models::state::TrackingState;
-use crate::models::screen::ScreenContext;
-use crate::screenshot::capture_screenshot;
-use crate::ui_automation::element::{
-    initialize_automation,
-    get_element_info,
+use crate::models::element_info::ScrollDirection;
+use crate::models::state::{TrackingState, WindowState, SettingsState, STATE_RUNNING, STATE_PAUSED, STATE_STOPPED};
+use crate::commands::tracking::{pause_recording, resume_recording, finish_recording, set_paused};
+use crate::models::session::{IdleNotice, OutOfScopeAction};
+use crate::models::settings::{IdleConfig, NavigationConfig};
+use crate::screenshot::capture_frame;
+use crate::ui_automation::automation::{Automation, CapturedElement};
+use crate::ui_automation::backend::{AccessibilityBackend, AccessibleNode};
+use crate::ui_automation::element::find_visible_tooltip;
+use crate::ui_automation::window::{
+    window_center, window_context_at, foreground_window_context, top_level_window_context, WindowContext,
 };
+use super::exclusion::ExclusionFilter;
+use super::hooks::HookInputSource;
+use super::hotkeys::{HotkeyAction, HotkeyBindings};
+use super::input::{InputEvent, InputSource, MouseButton};
+use super::interpreter::{InputInterpreter, TrackedAction, DOUBLE_CLICK_TIME};
+use super::menus::OpenMenus;
+use super::ui_events::{UiEvent, UiEventWatcher};
+use super::pipeline::{CapturedStep, Pipeline, PipelineJob, PipelineMetrics, StepRequest};
 
-// App identifiers
-const APP_WINDOW_IDENTIFIERS: &[&str] = &[
-    "MataPass",  // Your window title
-];
+// How long the loop blocks waiting for input, and how often it ticks
+const EVENT_WAIT_TIMEOUT: Duration = Duration::from_millis(100);
 
-// Virtual Key Codes
-const VK_LBUTTON: i32 = 0x01;
-const VK_RBUTTON: i32 = 0x02;
-const VK_TAB: i32 = 0x09;
-const VK_RETURN: i32 = 0x0D;
-const VK_ESCAPE: i32 = 0x1B;
-const VK_SPACE: i32 = 0x20;
-const VK_LEFT: i32 = 0x25;
-const VK_UP: i32 = 0x26;
-const VK_RIGHT: i32 = 0x27;
-const VK_DOWN: i32 = 0x28;
-const VK_SEMICOLON: i32 = 0xBA; // Virtual key code for ';'
-const VK_MENU: i32 = 0x12;   // Virtual key code for Alt
+// Focus and window changes this soon after a mouse press were caused by
+// it, and the click step already shows them. The same goes for focus moved
+// by a key (Tab, arrows, Enter) and its keystroke step.
+const INPUT_CAUSED_WINDOW: Duration = Duration::from_millis(500);
 
+// An app that takes longer to say what's under the pointer costs the step
+// its element rather than holding up input handling
+const CAPTURE_LOOKUP_TIMEOUT: Duration = Duration::from_millis(250);
 
-const CLICK_DEBOUNCE_TIME: u128 = 50;
-const KEYSTROKE_DEBOUNCE_TIME: u128 = 150;
-
-
-static START_TIME: once_cell::sync::Lazy<Instant> = once_cell::sync::Lazy::new(Instant::now);
+fn caused_by_press(press: Option<Instant>, time: Instant) -> bool {
+    press.is_some_and(|press| time.saturating_duration_since(press) < INPUT_CAUSED_WINDOW)
+}
 
-fn get_timestamp() -> u128 {
-    START_TIME.elapsed().as_millis()
+fn main_window(app_handle: &AppHandle) -> Result<Window, Error> {
+    app_handle.get_window("main")
+        .ok_or_else(|| Error::WindowError("Main window not found".to_string()))
 }
 
-#[derive(Debug)]
-struct EventEmitter {
-    last_click_time: Instant,
-    last_keystroke_time: Instant,
-    last_key_states: HashMap<i32, bool>,
+pub struct InputTracker {
+    interpreter: InputInterpreter,
+    // Looks up elements as input happens; describing them is left to the pipeline
+    automation: Automation,
+    pipeline: Pipeline,
+    tracking_state: TrackingState,
+    // Left clicks wait here until we know whether a second click follows
+    pending_click: Option<(CapturedStep, Instant)>,
+    // Where the current typing run goes, looked up at its first key
+    text_target: Option<CapturedElement>,
+    menus: OpenMenus,
+    last_mouse_press: Option<Instant>,
+    last_key_press: Option<Instant>,
+    // Foreground changes repeat when windows flash or reactivate
+    last_foreground: Option<isize>,
+    settings: SettingsState,
+    settings_revision: Option<u64>,
+    exclusions: ExclusionFilter,
+    idle: IdleConfig,
+    last_activity: Instant,
+    // Paused by idle detection rather than by the user
+    idle_paused: bool,
+    // Input this soon after recording starts or resumes is ignored
+    discard_input: Duration,
+    // Whether the last check found recording in that window
+    pre_roll: bool,
+    navigation: NavigationConfig,
 }
 
-impl EventEmitter {
-    fn new() -> Self {
-        println!("[INPUT][{}ms] Initializing event emitter", get_timestamp());
+impl InputTracker {
+    pub fn new(
+        automation: Automation,
+        pipeline: Pipeline,
+        tracking_state: TrackingState,
+        settings: SettingsState,
+        menus: OpenMenus,
+    ) -> Self {
+        tracing::debug!("Initializing input tracker");
         Self {
-            last_click_time: Instant::now(),
-            last_keystroke_time: Instant::now(),
-            last_key_states: HashMap::new(),
+            interpreter: InputInterpreter::new(),
+            automation,
+            pipeline,
+            tracking_state,
+            pending_click: None,
+            text_target: None,
+            menus,
+            last_mouse_press: None,
+            last_key_press: None,
+            last_foreground: None,
+            settings,
+            settings_revision: None,
+            exclusions: ExclusionFilter::default(),
+            idle: IdleConfig::default(),
+            last_activity: Instant::now(),
+            idle_paused: false,
+            discard_input: Duration::ZERO,
+            pre_roll: false,
+            navigation: NavigationConfig::default(),
         }
     }
 
-    fn emit_event(&self, app_handle: &AppHandle, info: ElementInfo) -> Result<(), Error> {
-        println!("[INPUT][{}ms] Emitting {} event at ({:?}, {:?})", 
-            get_timestamp(),
-            info.action_type, 
-            info.x, 
-            info.y
-        );
-        app_handle.emit_all("element_interaction", info)
-            .map_err(|e| Error::EmitError(e.to_string()))
+    /// Picks up settings changed through commands since the last event.
+    fn refresh_settings(&mut self) {
+        let revision = self.settings.revision();
+        if self.settings_revision == Some(revision) {
+            return;
+        }
+        self.settings_revision = Some(revision);
+
+        let settings = self.settings.get();
+        match HotkeyBindings::try_from(&settings.hotkeys) {
+            Ok(hotkeys) => self.interpreter.set_hotkeys(hotkeys),
+            Err(e) => tracing::warn!(error = %e, "Invalid hotkeys in settings, keeping previous"),
+        }
+        match ExclusionFilter::new(&settings.exclusions) {
+            Ok(exclusions) => self.exclusions = exclusions,
+            Err(e) => tracing::warn!(error = %e, "Invalid exclusion rules in settings, keeping previous"),
+        }
+        self.idle = settings.idle;
+        self.discard_input = Duration::from_millis(settings.countdown.discard_input_ms);
+        self.navigation = settings.navigation;
+        let hover_dwell = settings.hover.enabled.then(|| Duration::from_millis(settings.hover.dwell_ms));
+        self.interpreter.set_hover_dwell(hover_dwell);
     }
-}
 
-pub struct InputTracker {
-    emitter: EventEmitter,
-    automation: IUIAutomation,
-}
+    /// `None` if the window must not be recorded, either because it's
+    /// excluded or because it's outside an ignoring target scope. Otherwise
+    /// whether the step should be tagged as out of scope.
+    fn check_window(&self, window: Option<WindowContext>) -> Option<bool> {
+        if let Some(window) = &window {
+            if let Some(reason) = self.exclusions.excluded_by(window) {
+                tracing::debug!(process = %window.process_name, title = %window.window_title, %reason, "Skipping excluded window");
+                return None;
+            }
+        }
 
-impl InputTracker {
-    pub fn new() -> Result<Self, Error> {
-        println!("[INPUT][{}ms] Initializing input tracker", get_timestamp());
-        let automation = initialize_automation()?;
-        println!("[INPUT][{}ms] UI Automation initialized successfully", get_timestamp());
-        
-        Ok(Self {
-            emitter: EventEmitter::new(),
-            automation,
-        })
+        let scope = match self.tracking_state.with_session(|session| session.scope.clone()).flatten() {
+            Some(scope) => scope,
+            None => return Some(false),
+        };
+        if window.as_ref().is_some_and(|window| scope.contains(window)) {
+            return Some(false);
+        }
+
+        match scope.out_of_scope {
+            OutOfScopeAction::Tag => Some(true),
+            OutOfScopeAction::Ignore => {
+                let title = window.map(|window| window.window_title).unwrap_or_default();
+                tracing::debug!(%title, "Ignoring window outside the target scope");
+                None
+            }
+        }
     }
 
-    fn should_skip_window(window_info: &ElementInfo) -> bool {
-        println!("\n=== Window Info ===");
-        println!("Window Title: {}", window_info.window_title);
-        println!("Window Class: {}", window_info.class_name);
-        
-        let should_skip = APP_WINDOW_IDENTIFIERS.iter().any(|&identifier| {
-            let matches = window_info.window_title.to_lowercase().contains(&identifier.to_lowercase());
-            println!("Checking identifier: {}", identifier);
-            println!("Should skip: {}", matches);
-            matches
-        });
-        
-        println!("Final decision - Skip: {}", should_skip);
-        println!("=================\n");
-        
-        should_skip
+    fn check_window_at(&self, point: POINT) -> Option<bool> {
+        self.check_window(window_context_at(point.x, point.y))
     }
 
-    fn get_focused_element_position(&self) -> Option<(i32, i32)> {
-        unsafe {
-            if let Ok(focused_element) = self.automation.GetFocusedElement() {
-                if let Ok(rect_variant) = focused_element.GetCurrentPropertyValue(UIA_BoundingRectanglePropertyId) {
-                    let rect_array = rect_variant.Anonymous.Anonymous.Anonymous.parray;
-                    if !rect_array.is_null() {
-                        let rect_data = &*rect_array;
-                        if rect_data.rgsabound[0].cElements >= 4 {
-                            let elements = std::slice::from_raw_parts(
-                                rect_data.pvData as *const f64,
-                                4
-                            );
-                            let x = elements[0] as i32 + (elements[2] as i32 / 2);
-                            let y = elements[1] as i32 + (elements[3] as i32 / 2);
-                            return Some((x, y));
-                        }
-                    }
-                }
+    /// Keyboard steps go to the foreground window. Returns the point whose
+    /// monitor should be captured and the scope flag, or `None` if the
+    /// window isn't recorded.
+    fn keyboard_capture_point(&self, fallback_point: POINT) -> Option<(POINT, bool)> {
+        let window = foreground_window_context();
+        let center = window.as_ref().and_then(|window| window_center(window.hwnd));
+        let out_of_scope = self.check_window(window)?;
+        Some((center.map(|(x, y)| POINT { x, y }).unwrap_or(fallback_point), out_of_scope))
+    }
+
+    /// The element under `point`, looked up before the UI reacts to the
+    /// input. Just the one cached lookup: this runs on the input thread, so
+    /// the resolve worker finds the control it belongs to.
+    fn capture_element_at(&self, point: POINT) -> Option<CapturedElement> {
+        let started = Instant::now();
+        let element = self.automation.element_from_point(point.x, point.y);
+        self.pipeline.record_capture(started.elapsed());
+        element.map(CapturedElement::new)
+    }
+
+    /// Keyboard input goes to the focused element, or failing that to
+    /// whatever is under the cursor.
+    fn capture_focused_element(&self, fallback_point: POINT) -> Option<CapturedElement> {
+        let started = Instant::now();
+        let element = self.automation.focused_element();
+        self.pipeline.record_capture(started.elapsed());
+        element.map(CapturedElement::new).or_else(|| self.capture_element_at(fallback_point))
+    }
+
+    /// Text of the tooltip showing for `hwnd`, the window under the pointer.
+    fn capture_tooltip(&self, hwnd: Option<isize>) -> Option<String> {
+        let started = Instant::now();
+        let window = hwnd.and_then(|hwnd| self.automation.element_from_handle(hwnd));
+        let tooltip = find_visible_tooltip(&self.automation, window.as_ref());
+        self.pipeline.record_capture(started.elapsed());
+        tooltip
+    }
+
+    fn submit_step(&self, step: CapturedStep) {
+        if !self.pipeline.submit(PipelineJob::Step(step)) {
+            tracing::warn!("Step was dropped by the pipeline");
+        }
+    }
+
+    fn handle_click(&mut self, point: POINT, button: MouseButton) {
+        // Debouncing already happened in the interpreter
+        tracing::debug!(x = point.x, y = point.y, "{}", button.click_action_type());
+
+        let out_of_scope = match self.check_window_at(point) {
+            Some(out_of_scope) => out_of_scope,
+            None => return,
+        };
+
+        // Capture the frame IMMEDIATELY, before the UI reacts to the click,
+        // then the element under it. The pipeline only describes it.
+        let frame = capture_frame(point.x, point.y);
+        let element = self.capture_element_at(point);
+        // Menus close as soon as the click lands, so read them now too
+        let menu = self.menus.item_at(point.x, point.y);
+
+        // Left clicks may turn into a double-click; hold them until we know
+        let step = CapturedStep::new(StepRequest::Click { point, button, menu }, element, frame, point, out_of_scope);
+        if button == MouseButton::Left {
+            self.pending_click = Some((step, Instant::now()));
+        } else {
+            self.submit_step(step);
+        }
+    }
+
+    fn handle_double_click(&mut self, point: POINT) {
+        tracing::debug!(x = point.x, y = point.y, "double_click");
+
+        // The first click's frame and element show the UI before it reacted,
+        // so reuse them
+        let step = match self.pending_click.take() {
+            Some((mut step, _)) => {
+                step.request = StepRequest::DoubleClick { point };
+                step
             }
-            None
+            None => {
+                let out_of_scope = match self.check_window_at(point) {
+                    Some(out_of_scope) => out_of_scope,
+                    None => return,
+                };
+                let frame = capture_frame(point.x, point.y);
+                let element = self.capture_element_at(point);
+                CapturedStep::new(StepRequest::DoubleClick { point }, element, frame, point, out_of_scope)
+            }
+        };
+        self.submit_step(step);
+    }
+
+    fn flush_pending_click(&mut self) {
+        if let Some((step, _)) = self.pending_click.take() {
+            self.submit_step(step);
         }
     }
 
-    fn handle_click(&mut self, app_handle: &AppHandle, point: POINT, is_right_click: bool) -> Result<(), Error> {
-        // 1. Check if we should process this click (debouncing)
-        let now = Instant::now();
-        if now.duration_since(self.emitter.last_click_time).as_millis() <= CLICK_DEBOUNCE_TIME {
-            return Ok(());
+    fn handle_drag(&mut self, button: MouseButton, start: (i32, i32), end: (i32, i32), path: Vec<(i32, i32)>) {
+        tracing::debug!(?start, ?end, "Drag");
+
+        let out_of_scope = match self.check_window_at(POINT { x: start.0, y: start.1 }) {
+            Some(out_of_scope) => out_of_scope,
+            None => return,
+        };
+
+        let frame = capture_frame(end.0, end.1);
+        let end_point = POINT { x: end.0, y: end.1 };
+        let element = self.capture_element_at(POINT { x: start.0, y: start.1 });
+        let drop_target = self.capture_element_at(end_point);
+        let request = StepRequest::Drag { button, start, end, path, drop_target };
+        self.submit_step(CapturedStep::new(request, element, frame, end_point, out_of_scope));
+    }
+
+    fn handle_scroll(&mut self, point: POINT, direction: ScrollDirection, amount: i32) {
+        tracing::debug!(?direction, amount, x = point.x, y = point.y, "Scroll");
+
+        let out_of_scope = match self.check_window_at(point) {
+            Some(out_of_scope) => out_of_scope,
+            None => return,
+        };
+
+        let frame = capture_frame(point.x, point.y);
+        // The container that scrolled is found from it in the resolve worker
+        let element = self.capture_element_at(point);
+        self.submit_step(CapturedStep::new(StepRequest::Scroll { point, direction, amount }, element, frame, point, out_of_scope));
+    }
+
+    fn handle_hover(&mut self, point: POINT) {
+        tracing::debug!(x = point.x, y = point.y, "Hover");
+
+        let window = window_context_at(point.x, point.y);
+        let hwnd = window.as_ref().map(|window| window.hwnd);
+        let out_of_scope = match self.check_window(window) {
+            Some(out_of_scope) => out_of_scope,
+            None => return,
+        };
+
+        // By now any tooltip has appeared, so the frame shows it. It can
+        // disappear again at any moment, so its text is read right away too.
+        let frame = capture_frame(point.x, point.y);
+        let element = self.capture_element_at(point);
+        let tooltip = self.capture_tooltip(hwnd);
+        self.submit_step(CapturedStep::new(StepRequest::Hover { point, tooltip }, element, frame, point, out_of_scope));
+    }
+
+    fn handle_window_activated(&mut self, hwnd: isize, element: Option<CapturedElement>) {
+        let window = top_level_window_context(hwnd);
+        tracing::debug!(title = ?window.as_ref().map(|window| &window.window_title), "Window activated");
+
+        let center = window.as_ref().and_then(|window| window_center(window.hwnd));
+        let out_of_scope = match self.check_window(window) {
+            Some(out_of_scope) => out_of_scope,
+            None => return,
+        };
+
+        let point = center.map(|(x, y)| POINT { x, y }).unwrap_or_default();
+        // Keep capture order; a click still held for a double-click came first
+        self.flush_pending_click();
+        let frame = capture_frame(point.x, point.y);
+        self.submit_step(CapturedStep::new(StepRequest::WindowActivated, element, frame, point, out_of_scope));
+    }
+
+    fn handle_focus_changed(&mut self, hwnd: isize, element: CapturedElement) {
+        tracing::debug!("Focus changed");
+
+        let window = top_level_window_context(hwnd);
+        let center = window.as_ref().and_then(|window| window_center(window.hwnd));
+        let out_of_scope = match self.check_window(window) {
+            Some(out_of_scope) => out_of_scope,
+            None => return,
+        };
+
+        let point = center.map(|(x, y)| POINT { x, y }).unwrap_or_default();
+        self.flush_pending_click();
+        let frame = capture_frame(point.x, point.y);
+        self.submit_step(CapturedStep::new(StepRequest::FocusChanged, Some(element), frame, point, out_of_scope));
+    }
+
+    /// `target` is the element the input went to if it is already known,
+    /// otherwise it's whatever has focus now.
+    fn handle_keyboard_step(&mut self, request: StepRequest, target: Option<CapturedElement>, fallback_point: POINT) {
+        if let Some((capture_point, out_of_scope)) = self.keyboard_capture_point(fallback_point) {
+            let frame = capture_frame(capture_point.x, capture_point.y);
+            let element = target.or_else(|| self.capture_focused_element(fallback_point));
+            self.submit_step(CapturedStep::new(request, element, frame, fallback_point, out_of_scope));
         }
-    
-        let click_type = if is_right_click { "right_click" } else { "click" };
-        println!("[INPUT][{}ms] {} at ({}, {})", get_timestamp(), click_type, point.x, point.y);
-        
-        // 2. Capture screenshot IMMEDIATELY after detecting click
-        // This happens before any processing or UI changes can occur
-        let screenshot = capture_screenshot(point.x, point.y);
-        
-        // 3. Get element info and process the click
-        if let Some(mut element_info) = get_element_info(point.x, point.y) {
-            if Self::should_skip_window(&element_info) {
-                println!("[INPUT][{}ms] Skipping app window click", get_timestamp());
-                return Ok(());
+    }
+
+    fn handle_hotkey(&mut self, app_handle: &AppHandle, hotkey: HotkeyAction, tracking_state: &TrackingState) -> Result<(), Error> {
+        tracing::info!(?hotkey, "Hotkey pressed");
+
+        let main_window = || main_window(app_handle);
+
+        match (hotkey, tracking_state.get_state()) {
+            (HotkeyAction::Capture, STATE_RUNNING) => {
+                self.handle_manual_screenshot();
+                Ok(())
             }
-    
-            println!("[INPUT][{}ms] Clicked {} element", get_timestamp(), element_info.control_type);
-            element_info.action_category = ActionCategory::Click;
-            element_info.action_type = click_type.to_string();
-            // 4. Use the screenshot we captured earlier
-            element_info.screenshot = screenshot;
-            
-            // 5. Small delay to ensure UI state is stable
-            std::thread::sleep(std::time::Duration::from_millis(50));
-            
-            // 6. Emit the event with everything prepared
-            self.emitter.emit_event(app_handle, element_info)?;
+            (HotkeyAction::PauseResume, STATE_RUNNING) => {
+                pause_recording(&main_window()?, tracking_state).map_err(Error::WindowError)
+            }
+            (HotkeyAction::PauseResume, STATE_PAUSED) => {
+                resume_recording(&main_window()?, tracking_state).map_err(Error::WindowError)
+            }
+            (HotkeyAction::Stop, state) if state != STATE_STOPPED => {
+                self.interpreter.discard_text();
+                self.text_target = None;
+                let window_state = app_handle.state::<WindowState>();
+                finish_recording(&main_window()?, tracking_state, &window_state).map_err(Error::WindowError)
+            }
+            (HotkeyAction::DiscardLastStep, state) if state != STATE_STOPPED => {
+                // A click still waiting for a possible double-click was never sent
+                if self.pending_click.take().is_none() {
+                    self.pipeline.submit(PipelineJob::DiscardLastStep);
+                }
+                Ok(())
+            }
+            _ => Ok(()),
         }
-    
-        // 7. Update last click time
-        self.emitter.last_click_time = now;
-        Ok(())
     }
 
-    fn handle_keystroke(&mut self, app_handle: &AppHandle, key_code: i32, fallback_point: POINT) -> Result<(), Error> {
-        let now = Instant::now();
-        
-        let current_state = unsafe { GetAsyncKeyState(key_code) };
-        let key_is_pressed = current_state < 0;
-        let was_pressed = self.emitter.last_key_states.get(&key_code).copied().unwrap_or(false);
-        
-        self.emitter.last_key_states.insert(key_code, key_is_pressed);
-        
-        if !was_pressed && 
-           key_is_pressed && 
-           now.duration_since(self.emitter.last_keystroke_time).as_millis() > KEYSTROKE_DEBOUNCE_TIME {
-            
-            let action_type = match key_code {
-                VK_TAB => "tab",
-                VK_RETURN => "enter",
-                VK_SPACE => "space",
-                VK_ESCAPE => "escape",
-                VK_LEFT => "arrow_left",
-                VK_UP => "arrow_up",
-                VK_RIGHT => "arrow_right",
-                VK_DOWN => "arrow_down",
-                _ => return Ok(()),
-            };
-
-            println!("[INPUT][{}ms] Keystroke: {}", get_timestamp(), action_type);
-
-            let (x, y) = self.get_focused_element_position()
-                .unwrap_or((fallback_point.x, fallback_point.y));
-
-            if let Some(mut element_info) = get_element_info(x, y) {
-                if Self::should_skip_window(&element_info) {
-                    return Ok(());
+    fn handle_manual_screenshot(&mut self) {
+        tracing::debug!("Manual screenshot capture");
+        let frame = capture_frame(0, 0);
+        self.submit_step(CapturedStep::new(StepRequest::Manual, None, frame, POINT { x: 0, y: 0 }, false));
+    }
+
+    /// Feeds one event to the interpreter and records the resulting action.
+    /// Events are interpreted even while not running so held modifiers stay
+    /// in sync across pause/resume.
+    pub fn track_input(&mut self, app_handle: &AppHandle, event: InputEvent, tracking_state: &TrackingState) -> Result<(), Error> {
+        self.refresh_settings();
+        self.last_activity = Instant::now();
+        match event {
+            InputEvent::MousePress { time, .. } => self.last_mouse_press = Some(time),
+            InputEvent::KeyPress { time, .. } => self.last_key_press = Some(time),
+            _ => {}
+        }
+        if self.idle_paused && tracking_state.get_state() != STATE_PAUSED {
+            // Resumed or stopped some other way
+            self.idle_paused = false;
+        }
+
+        // Wake on a release so the whole gesture happens while still paused
+        // and isn't recorded
+        let wakes = matches!(event, InputEvent::MouseRelease { .. } | InputEvent::KeyRelease { .. });
+        let in_pre_roll = self.in_pre_roll(tracking_state);
+        let actions = self.interpreter.handle_event(event);
+        if in_pre_roll {
+            // Still the tail of switching to the target app; drop partial scrolls too
+            self.interpreter.flush();
+        }
+        if !tracking_state.is_running() || in_pre_roll {
+            self.interpreter.discard_text();
+            self.text_target = None;
+            // Hotkeys still work while paused, otherwise there'd be no way to resume
+            for action in actions {
+                if let TrackedAction::Hotkey(hotkey) = action {
+                    self.handle_hotkey(app_handle, hotkey, tracking_state)?;
                 }
+            }
+            if wakes && self.idle_paused && !self.idle.resume_requires_hotkey {
+                self.resume_from_idle(app_handle, tracking_state)?;
+            }
+            return Ok(());
+        }
 
-                element_info.action_category = ActionCategory::Keystroke;
-                element_info.action_type = action_type.to_string();
-                self.emitter.emit_event(app_handle, element_info)?;
+        self.record_actions(app_handle, actions, tracking_state)
+    }
+
+    /// Turns focus and window changes into steps when the settings ask for
+    /// them. They don't count as activity for the idle timeout, since apps
+    /// can change focus by themselves.
+    pub fn track_ui_event(&mut self, event: UiEvent, tracking_state: &TrackingState) {
+        self.refresh_settings();
+        let recording = tracking_state.is_running() && !self.in_pre_roll(tracking_state);
+
+        match event {
+            UiEvent::WindowActivated { hwnd, element, time } => {
+                let switched = self.last_foreground.replace(hwnd) != Some(hwnd);
+                if recording && switched && self.navigation.window_switches && !caused_by_press(self.last_mouse_press, time) {
+                    self.handle_window_activated(hwnd, element);
+                }
+            }
+            // Coming back from our window to the same app is still a switch
+            UiEvent::OwnWindowActivated { hwnd } => self.last_foreground = Some(hwnd),
+            UiEvent::FocusChanged { hwnd, element, time } => {
+                let caused_by_input = caused_by_press(self.last_mouse_press, time) || caused_by_press(self.last_key_press, time);
+                // Moving through menus ends up as the menu path of the click
+                if recording && self.navigation.focus_changes && !caused_by_input && self.menus.is_empty() {
+                    self.handle_focus_changed(hwnd, element);
+                }
             }
+        }
+    }
+
+    /// Called when no input arrived for a while, to report actions that only
+    /// complete with time (settled scrolls, clicks that stayed single).
+    pub fn tick(&mut self, app_handle: &AppHandle, tracking_state: &TrackingState) -> Result<(), Error> {
+        self.refresh_settings();
+        let in_pre_roll = self.in_pre_roll(tracking_state);
+        let actions = self.interpreter.tick(Instant::now());
+        if tracking_state.is_running() && !in_pre_roll {
+            self.record_actions(app_handle, actions, tracking_state)?;
+        }
 
-            self.emitter.last_keystroke_time = now;
+        let click_settled = self.pending_click.as_ref()
+            .is_some_and(|(_, time)| time.elapsed().as_millis() > DOUBLE_CLICK_TIME);
+        if click_settled {
+            self.flush_pending_click();
         }
 
+        let idle_timeout = Duration::from_secs(self.idle.timeout_secs);
+        if self.idle.enabled && tracking_state.is_running() && self.last_activity.elapsed() >= idle_timeout {
+            self.pause_for_idle(app_handle, tracking_state)?;
+        }
         Ok(())
     }
 
-    fn handle_manual_screenshot(&mut self, app_handle: &AppHandle) -> Result<(), Error> {
-        let now = Instant::now();
-        if now.duration_since(self.emitter.last_keystroke_time).as_millis() <= KEYSTROKE_DEBOUNCE_TIME {
-            return Ok(());
+    /// Whether input is still ignored after recording started or resumed.
+    /// Gestures begun in that time are dropped once it's over, so a drag or
+    /// a typing run can't end up half recorded.
+    fn in_pre_roll(&mut self, tracking_state: &TrackingState) -> bool {
+        let in_pre_roll = tracking_state.running_for().is_some_and(|running| running < self.discard_input);
+        if self.pre_roll && !in_pre_roll {
+            self.interpreter.reset();
         }
-    
-        println!("[INPUT][{}ms] Manual screenshot capture", get_timestamp());
-    
-        // Create element info for manual screenshot
-        let element_info = ElementInfo {
-            x: None,  // No position for manual screenshot
-            y: None,  // No position for manual screenshot
-            screen_context: ScreenContext::new(),  // Using ScreenContext struct
-            name: "Manual Screenshot".to_string(),
-            control_type: "Screenshot".to_string(),
-            automation_id: String::new(),
-            class_name: String::new(),
-            window_title: "Manual Capture".to_string(),
-            parent_name: String::new(),
-            action_type: "capture".to_string(),
-            action_category: ActionCategory::Manual,
-            timestamp: chrono::Utc::now().to_rfc3339(),
-            screenshot: capture_screenshot(0,0),
-            value: String::new(),
-            state: String::new(),
-            help_text: String::new(),
+        self.pre_roll = in_pre_roll;
+        in_pre_roll
+    }
+
+    fn pause_for_idle(&mut self, app_handle: &AppHandle, tracking_state: &TrackingState) -> Result<(), Error> {
+        let idle_secs = self.last_activity.elapsed().as_secs();
+        tracing::info!(idle_secs, "No input for a while, pausing");
+
+        // Whatever was in progress before the user left is still a step
+        let actions = self.interpreter.flush();
+        self.record_actions(app_handle, actions, tracking_state)?;
+        self.flush_pending_click();
+
+        let window = main_window(app_handle)?;
+        if self.idle.resume_requires_hotkey {
+            // They'll have to come back to us to continue, so show the window
+            pause_recording(&window, tracking_state).map_err(Error::WindowError)?;
+        } else {
+            set_paused(&window, tracking_state, true).map_err(Error::WindowError)?;
+        }
+        self.idle_paused = true;
+
+        let notice = IdleNotice {
+            session_id: tracking_state.with_session(|session| session.id.clone()),
+            idle_secs,
+            resume_requires_hotkey: self.idle.resume_requires_hotkey,
         };
-    
-        self.emitter.emit_event(app_handle, element_info)?;
-        self.emitter.last_keystroke_time = now;
-        Ok(())
+        app_handle.emit_all("recording-idle", notice)
+            .map_err(|e| Error::EmitError(e.to_string()))
     }
 
-    pub fn track_inputs(&mut self, app_handle: &AppHandle) -> Result<(), Error> {
-        unsafe {
-            let mut point = POINT { x: 0, y: 0 };
-            GetCursorPos(&mut point);
+    fn resume_from_idle(&mut self, app_handle: &AppHandle, tracking_state: &TrackingState) -> Result<(), Error> {
+        tracing::info!("Input after idle pause, resuming");
+        self.idle_paused = false;
+        set_paused(&main_window(app_handle)?, tracking_state, false).map_err(Error::WindowError)
+    }
 
-            // Track mouse clicks
-            if GetAsyncKeyState(VK_LBUTTON) < 0 {
-                self.handle_click(app_handle, point, false)?;
-            }
-            if GetAsyncKeyState(VK_RBUTTON) < 0 {
-                self.handle_click(app_handle, point, true)?;
+    fn record_actions(&mut self, app_handle: &AppHandle, actions: Vec<TrackedAction>, tracking_state: &TrackingState) -> Result<(), Error> {
+        let (x, y) = self.interpreter.cursor_position();
+        let fallback_point = POINT { x, y };
+        for action in actions {
+            match action {
+                TrackedAction::DoubleClick { x, y } => {
+                    self.handle_double_click(POINT { x, y });
+                    continue;
+                }
+                TrackedAction::Hotkey(HotkeyAction::DiscardLastStep) => {
+                    self.handle_hotkey(app_handle, HotkeyAction::DiscardLastStep, tracking_state)?;
+                    continue;
+                }
+                _ => {}
             }
 
-            if (GetAsyncKeyState(VK_MENU) < 0) && (GetAsyncKeyState(VK_SEMICOLON) < 0) {
-                self.handle_manual_screenshot(app_handle)?;
-            }      
-
-            // Track important keystrokes
-            for &key_code in &[
-                VK_TAB,
-                VK_RETURN,
-                VK_SPACE,
-                VK_ESCAPE,
-                VK_LEFT,
-                VK_UP,
-                VK_RIGHT,
-                VK_DOWN,
-            ] {
-                self.handle_keystroke(app_handle, key_code, point)?;
+            // Anything else means the previous click was a single click
+            self.flush_pending_click();
+            match action {
+                TrackedAction::Click { x, y, button } => {
+                    self.handle_click(POINT { x, y }, button)
+                }
+                TrackedAction::Drag { button, start, end, path } => {
+                    self.handle_drag(button, start, end, path)
+                }
+                TrackedAction::Scroll { x, y, direction, amount } => {
+                    self.handle_scroll(POINT { x, y }, direction, amount)
+                }
+                TrackedAction::Keystroke { action_type } => {
+                    tracing::debug!(action_type, "Keystroke");
+                    self.handle_keyboard_step(StepRequest::Keystroke { action_type }, None, fallback_point)
+                }
+                TrackedAction::TextStarted => {
+                    self.text_target = self.capture_focused_element(fallback_point);
+                }
+                TrackedAction::TextInput { text } => {
+                    tracing::debug!(chars = text.chars().count(), "Text input");
+                    // Tab or a click may have moved focus on by now
+                    let target = self.text_target.take();
+                    let password = target.as_ref().is_some_and(|target| target.element().is_password());
+                    self.handle_keyboard_step(StepRequest::TextInput { text, password }, target, fallback_point)
+                }
+                TrackedAction::Shortcut { combo } => {
+                    tracing::debug!(%combo, "Shortcut");
+                    self.handle_keyboard_step(StepRequest::Shortcut { combo }, None, fallback_point)
+                }
+                TrackedAction::Hover { x, y } => self.handle_hover(POINT { x, y }),
+                TrackedAction::Hotkey(hotkey) => self.handle_hotkey(app_handle, hotkey, tracking_state)?,
+                TrackedAction::DoubleClick { .. } => {}
             }
         }
-
         Ok(())
     }
 }
 
-pub fn setup_tracking_loop(app_handle: AppHandle, tracking_state: TrackingState) {
-    println!("[INPUT][{}ms] Starting input tracking loop", get_timestamp());
+/// Runs the capture stage on its own thread: interprets input, snapshots
+/// frames and elements and hands steps to the pipeline, which describes
+/// and emits them.
+pub fn setup_tracking_loop(
+    app_handle: AppHandle,
+    tracking_state: TrackingState,
+    settings_state: SettingsState,
+    metrics: PipelineMetrics,
+) {
+    tracing::info!("Starting input tracking loop");
     std::thread::spawn(move || {
-        let mut tracker = match InputTracker::new() {
-            Ok(t) => t,
+        // Elements are looked up here, as input arrives
+        let automation = unsafe {
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            Automation::new()
+        };
+        let automation = match automation {
+            Ok(automation) => automation,
             Err(e) => {
-                eprintln!("[ERROR][{}ms] Failed to initialize input tracker: {}", get_timestamp(), e);
+                tracing::error!(error = %Error::from(e), "Failed to initialize UI Automation");
                 return;
             }
         };
+        automation.set_timeout(CAPTURE_LOOKUP_TIMEOUT);
+
+        let pipeline = Pipeline::start(app_handle.clone(), tracking_state.clone(), settings_state.clone(), metrics);
+        let menus = OpenMenus::default();
+        let mut tracker = InputTracker::new(automation, pipeline, tracking_state.clone(), settings_state, menus.clone());
+
+        // Without it steps just lose menu paths and navigation steps
+        let ui_events = UiEventWatcher::start(menus)
+            .inspect_err(|e| tracing::warn!(error = %e, "Failed to watch UI events"))
+            .ok();
 
+        let mut source = match HookInputSource::start() {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to install input hooks");
+                return;
+            }
+        };
+
+        // Mouse moves keep events flowing, so ticks run on their own schedule
+        let mut last_tick = Instant::now();
         loop {
-            if tracking_state.is_running() {
-                if let Err(e) = tracker.track_inputs(&app_handle) {
-                    eprintln!("[ERROR][{}ms] Error tracking inputs: {}", get_timestamp(), e);
+            if let Some(event) = source.next_event(EVENT_WAIT_TIMEOUT) {
+                if let Err(e) = tracker.track_input(&app_handle, event, &tracking_state) {
+                    tracing::error!(error = %e, "Error tracking inputs");
+                }
+            }
+            while let Some(event) = ui_events.as_ref().and_then(|watcher| watcher.next_event()) {
+                tracker.track_ui_event(event, &tracking_state);
+            }
+            if last_tick.elapsed() >= EVENT_WAIT_TIMEOUT {
+                if let Err(e) = tracker.tick(&app_handle, &tracking_state) {
+                    tracing::error!(error = %e, "Error tracking inputs");
                 }
+                last_tick = Instant::now();
             }
-            std::thread::sleep(std::time::Duration::from_millis(10));
         }
     });
 }
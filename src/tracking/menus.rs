@@ -0,0 +1,62 @@
+use std::sync::Arc;
+use parking_lot::Mutex;
+
+use crate::models::element_info::BoundingRect;
+use crate::ui_automation::menu::OpenMenu;
+
+/// A click on a menu item, read from the open menus at the moment of the
+/// click.
+#[derive(Debug, Clone)]
+pub struct CapturedMenu {
+    /// Opening items first, the clicked item last, e.g. ["File", "Save As"]
+    pub path: Vec<String>,
+    pub item_bounds: BoundingRect,
+    /// Opened by right-clicking something rather than from a menu bar
+    pub context_menu: bool,
+}
+
+/// Menus currently showing, outermost first. `UiEventWatcher` keeps it up
+/// to date; the capture stage reads it on every click.
+#[derive(Clone, Default)]
+pub struct OpenMenus(Arc<Mutex<Vec<OpenMenu>>>);
+
+impl OpenMenus {
+    pub(super) fn opened(&self, menu: OpenMenu) {
+        let mut menus = self.0.lock();
+        // A submenu replaces whatever was open past the menu its item is
+        // in; anything else starts a new chain
+        let parent = menu.opener.as_ref().and_then(|opener| {
+            let (x, y) = opener.bounds.center();
+            menus.iter().rposition(|open| open.item_at(x, y).is_some())
+        });
+        menus.truncate(parent.map_or(0, |index| index + 1));
+        menus.push(menu);
+    }
+
+    pub(super) fn closed(&self, hwnd: isize) {
+        self.0.lock().retain(|menu| menu.hwnd != hwnd);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().is_empty()
+    }
+
+    /// The menu item at a point, with the items that lead to it.
+    pub fn item_at(&self, x: i32, y: i32) -> Option<CapturedMenu> {
+        let menus = self.0.lock();
+        let (depth, item) = menus.iter().enumerate().rev()
+            .find_map(|(depth, menu)| menu.item_at(x, y).map(|item| (depth, item)))?;
+        let chain = &menus[..=depth];
+
+        let mut path: Vec<String> = chain.iter()
+            .filter_map(|menu| menu.opener.as_ref())
+            .map(|opener| opener.name.clone())
+            .collect();
+        path.push(item.name.clone());
+        Some(CapturedMenu {
+            path,
+            item_bounds: item.bounds,
+            context_menu: chain[0].opener.is_none(),
+        })
+    }
+}
@@ -1 +1,11 @@
-pub mod loop_handler;
\ No newline at end of file
+pub mod loop_handler;
+pub mod input;
+pub mod interpreter;
+pub mod keys;
+pub mod hooks;
+pub mod menus;
+pub mod ui_events;
+pub mod chord;
+pub mod hotkeys;
+pub mod exclusion;
+pub mod pipeline;
\ No newline at end of file
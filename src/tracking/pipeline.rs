@@ -0,0 +1,817 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use windows::Win32::Foundation::POINT;
+use windows::Win32::System::Com::{CoInitializeEx, COINIT_MULTITHREADED};
+
+use crate::journal;
+use crate::models::error::Error;
+use crate::models::element_info::{
+    ElementInfo, ActionCategory, ElementSummary, DragDetails, MenuPath, ScrollDetails, ScrollDirection,
+};
+use crate::models::screen::ScreenContext;
+use crate::models::session::StepProgress;
+use crate::models::settings::{PasswordRedaction, RedactionConfig};
+use crate::models::state::{SettingsState, TrackingState, WindowState, STATE_STOPPED};
+use crate::commands::tracking::finish_recording;
+use crate::screenshot::{encode_frame, RawFrame};
+use crate::ui_automation::automation::{Automation, CapturedElement};
+use crate::ui_automation::backend::AccessibleNode;
+use crate::ui_automation::element::{build_element_info, interactive_element, scroll_container};
+use super::input::MouseButton;
+use super::menus::CapturedMenu;
+
+// Capture -> resolve and resolve -> emit. Frames are large, so keep these short
+const RESOLVE_QUEUE_CAPACITY: usize = 8;
+const EMIT_QUEUE_CAPACITY: usize = 8;
+
+// How long the capture stage waits for room before dropping a step. It must
+// not block for long or hotkeys (including stop) stop responding.
+const ENQUEUE_TIMEOUT: Duration = Duration::from_millis(500);
+const ENQUEUE_RETRY_INTERVAL: Duration = Duration::from_millis(5);
+
+// Warn once this few steps are left before the limit
+const STEP_LIMIT_WARNING_REMAINING: u64 = 2;
+
+// Same length whatever was typed
+const PASSWORD_MASK: &str = "••••••••";
+
+/// What the user did, as seen by the capture stage. The element it was
+/// done to is captured along with it, in `CapturedStep::element`.
+#[derive(Debug)]
+pub enum StepRequest {
+    /// `menu` is set when the click landed on an item of an open menu
+    Click { point: POINT, button: MouseButton, menu: Option<CapturedMenu> },
+    DoubleClick { point: POINT },
+    Drag {
+        button: MouseButton,
+        start: (i32, i32),
+        end: (i32, i32),
+        path: Vec<(i32, i32)>,
+        drop_target: Option<CapturedElement>,
+    },
+    Scroll { point: POINT, direction: ScrollDirection, amount: i32 },
+    Keystroke { action_type: &'static str },
+    /// `password` says the run was typed into a password field, as seen
+    /// at its first key
+    TextInput { text: String, password: bool },
+    Shortcut { combo: String },
+    /// `tooltip` is the text of the tooltip showing when the dwell ended
+    Hover { point: POINT, tooltip: Option<String> },
+    FocusChanged,
+    WindowActivated,
+    Manual,
+}
+
+/// A step snapshotted at the moment of input, before the UI reacts.
+pub struct CapturedStep {
+    pub request: StepRequest,
+    /// The element acted on, as it was then: under the pointer for mouse
+    /// steps, the focused one for keyboard steps, and the one focused at
+    /// the first key for typed text. `None` for manual captures, or if
+    /// nothing could be looked up.
+    ///
+    /// This is the one UI Automation lookup done at capture time. It has
+    /// to happen then: by the time the resolve worker runs, a click may
+    /// have closed the dialog or moved focus, and the element would be
+    /// gone or a different one. Everything else (the control it belongs
+    /// to, the scrolling container, names and selectors) is worked out in
+    /// the resolve worker from this element.
+    pub element: Option<CapturedElement>,
+    pub frame: Option<RawFrame>,
+    /// Where steps on elements without bounds are placed
+    pub fallback_point: POINT,
+    pub timestamp: String,
+    /// For measuring how long the step took to reach the frontend
+    pub captured_at: Instant,
+    pub out_of_scope: bool,
+}
+
+impl CapturedStep {
+    /// Stamps the step with the current time.
+    pub fn new(
+        request: StepRequest,
+        element: Option<CapturedElement>,
+        frame: Option<RawFrame>,
+        fallback_point: POINT,
+        out_of_scope: bool,
+    ) -> Self {
+        Self {
+            request,
+            element,
+            frame,
+            fallback_point,
+            timestamp: get_timestamp(),
+            captured_at: Instant::now(),
+            out_of_scope,
+        }
+    }
+}
+
+pub enum PipelineJob {
+    Step(CapturedStep),
+    // Goes through the queues so it removes the newest step, not the newest emitted one
+    DiscardLastStep,
+}
+
+enum EmitJob {
+    Step(Box<ElementInfo>, Option<RawFrame>, Instant),
+    DiscardLastStep,
+}
+
+#[derive(Default)]
+struct Timing {
+    count: AtomicU64,
+    total_us: AtomicU64,
+    max_us: AtomicU64,
+    last_us: AtomicU64,
+}
+
+impl Timing {
+    fn record(&self, elapsed: Duration) {
+        let us = elapsed.as_micros() as u64;
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_us.fetch_add(us, Ordering::Relaxed);
+        self.max_us.fetch_max(us, Ordering::Relaxed);
+        self.last_us.store(us, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> TimingStats {
+        let count = self.count.load(Ordering::Relaxed);
+        let ms = |us: u64| us as f64 / 1000.0;
+        TimingStats {
+            last_ms: ms(self.last_us.load(Ordering::Relaxed)),
+            average_ms: if count == 0 { 0.0 } else { ms(self.total_us.load(Ordering::Relaxed)) / count as f64 },
+            max_ms: ms(self.max_us.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    captured: AtomicU64,
+    emitted: AtomicU64,
+    unresolved: AtomicU64,
+    dropped: AtomicU64,
+    late: AtomicU64,
+    backpressure_waits: AtomicU64,
+    resolve_queue_depth: AtomicUsize,
+    resolve_queue_peak: AtomicUsize,
+    emit_queue_depth: AtomicUsize,
+    emit_queue_peak: AtomicUsize,
+    capture_time: Timing,
+    resolve_time: Timing,
+    latency: Timing,
+}
+
+/// Counters shared by the pipeline stages, readable through
+/// `get_pipeline_metrics`.
+#[derive(Clone, Default)]
+pub struct PipelineMetrics {
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimingStats {
+    pub last_ms: f64,
+    pub average_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineStats {
+    pub captured: u64,
+    pub emitted: u64,
+    /// Steps where no element could be resolved
+    pub unresolved: u64,
+    /// Steps thrown away because the resolve queue stayed full
+    pub dropped: u64,
+    /// Steps that finished after their recording stopped or hit its limit
+    pub late: u64,
+    /// Times a stage found the next queue full and had to wait
+    pub backpressure_waits: u64,
+    pub resolve_queue_depth: usize,
+    pub resolve_queue_peak: usize,
+    pub emit_queue_depth: usize,
+    pub emit_queue_peak: usize,
+    /// Time the capture stage spent per UI Automation lookup
+    pub capture_time: TimingStats,
+    /// Time spent in UI Automation per resolved step
+    pub resolve_time: TimingStats,
+    /// From the input to the step reaching the frontend
+    pub latency: TimingStats,
+}
+
+impl PipelineMetrics {
+    pub fn snapshot(&self) -> PipelineStats {
+        let c = &self.counters;
+        PipelineStats {
+            captured: c.captured.load(Ordering::Relaxed),
+            emitted: c.emitted.load(Ordering::Relaxed),
+            unresolved: c.unresolved.load(Ordering::Relaxed),
+            dropped: c.dropped.load(Ordering::Relaxed),
+            late: c.late.load(Ordering::Relaxed),
+            backpressure_waits: c.backpressure_waits.load(Ordering::Relaxed),
+            resolve_queue_depth: c.resolve_queue_depth.load(Ordering::Relaxed),
+            resolve_queue_peak: c.resolve_queue_peak.load(Ordering::Relaxed),
+            emit_queue_depth: c.emit_queue_depth.load(Ordering::Relaxed),
+            emit_queue_peak: c.emit_queue_peak.load(Ordering::Relaxed),
+            capture_time: c.capture_time.snapshot(),
+            resolve_time: c.resolve_time.snapshot(),
+            latency: c.latency.snapshot(),
+        }
+    }
+}
+
+fn enqueued(depth: &AtomicUsize, peak: &AtomicUsize) {
+    let depth = depth.fetch_add(1, Ordering::Relaxed) + 1;
+    peak.fetch_max(depth, Ordering::Relaxed);
+}
+
+fn dequeued(depth: &AtomicUsize) {
+    depth.fetch_sub(1, Ordering::Relaxed);
+}
+
+fn get_timestamp() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Entry point of the pipeline, owned by the capture stage.
+pub struct Pipeline {
+    sender: SyncSender<PipelineJob>,
+    metrics: PipelineMetrics,
+    tracking_state: TrackingState,
+}
+
+impl Pipeline {
+    /// Spawns the resolve and emit workers. One worker per stage keeps
+    /// steps in the order they were captured.
+    pub fn start(
+        app_handle: AppHandle,
+        tracking_state: TrackingState,
+        settings: SettingsState,
+        metrics: PipelineMetrics,
+    ) -> Self {
+        let (sender, resolve_receiver) = sync_channel(RESOLVE_QUEUE_CAPACITY);
+        let (emit_sender, emit_receiver) = sync_channel(EMIT_QUEUE_CAPACITY);
+
+        let resolve_metrics = metrics.clone();
+        let resolve_state = tracking_state.clone();
+        std::thread::spawn(move || run_resolve_stage(resolve_receiver, emit_sender, settings, resolve_metrics, resolve_state));
+
+        let emit_metrics = metrics.clone();
+        let emit_state = tracking_state.clone();
+        std::thread::spawn(move || run_emit_stage(app_handle, emit_receiver, emit_metrics, emit_state));
+
+        Self { sender, metrics, tracking_state }
+    }
+
+    /// Records how long an element lookup held up the capture stage.
+    pub fn record_capture(&self, elapsed: Duration) {
+        self.metrics.counters.capture_time.record(elapsed);
+    }
+
+    /// Queues a job without blocking the capture stage for more than
+    /// `ENQUEUE_TIMEOUT`. Returns false if the job was dropped.
+    pub fn submit(&self, job: PipelineJob) -> bool {
+        let counters = &self.metrics.counters;
+        if matches!(job, PipelineJob::Step(_)) {
+            counters.captured.fetch_add(1, Ordering::Relaxed);
+        }
+        self.tracking_state.step_queued();
+        // Counted before sending so the worker can never see it go negative
+        enqueued(&counters.resolve_queue_depth, &counters.resolve_queue_peak);
+
+        let mut job = match self.sender.try_send(job) {
+            Ok(()) => return true,
+            Err(TrySendError::Full(job)) => job,
+            Err(TrySendError::Disconnected(_)) => {
+                dequeued(&counters.resolve_queue_depth);
+                self.tracking_state.step_finished();
+                return false;
+            }
+        };
+
+        counters.backpressure_waits.fetch_add(1, Ordering::Relaxed);
+        let deadline = Instant::now() + ENQUEUE_TIMEOUT;
+        while Instant::now() < deadline {
+            std::thread::sleep(ENQUEUE_RETRY_INTERVAL);
+            job = match self.sender.try_send(job) {
+                Ok(()) => return true,
+                Err(TrySendError::Full(job)) => job,
+                Err(TrySendError::Disconnected(_)) => break,
+            };
+        }
+
+        dequeued(&counters.resolve_queue_depth);
+        counters.dropped.fetch_add(1, Ordering::Relaxed);
+        self.tracking_state.step_finished();
+        tracing::warn!("Resolve queue full, dropped a step");
+        false
+    }
+}
+
+fn run_resolve_stage(
+    receiver: Receiver<PipelineJob>,
+    sender: SyncSender<EmitJob>,
+    settings: SettingsState,
+    metrics: PipelineMetrics,
+    tracking_state: TrackingState,
+) {
+    // One client for the life of the worker, with its cache request
+    let automation = unsafe {
+        // UI Automation needs COM on this thread
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+        Automation::new()
+    };
+    let automation = match automation {
+        Ok(automation) => automation,
+        Err(e) => {
+            tracing::error!(error = %Error::from(e), "Failed to initialize UI Automation");
+            return;
+        }
+    };
+
+    let counters = &metrics.counters;
+    let mut redaction = RedactionConfig::default();
+    let mut settings_revision = None;
+    // What a context menu, if the next click lands in one, was opened on
+    let mut right_clicked: Option<ElementSummary> = None;
+    for job in receiver {
+        dequeued(&counters.resolve_queue_depth);
+        let emit_job = match job {
+            PipelineJob::Step(step) => {
+                let revision = settings.revision();
+                if settings_revision != Some(revision) {
+                    settings_revision = Some(revision);
+                    redaction = settings.get().redaction;
+                }
+                let captured_at = step.captured_at;
+                let started = Instant::now();
+                let resolved = resolve_step(&automation, step, &redaction, right_clicked.as_ref());
+                if let Some((info, _)) = &resolved {
+                    // Submenus may be opened by hovering on the way to the item
+                    if !matches!(
+                        info.action_category,
+                        ActionCategory::Hover | ActionCategory::FocusChanged | ActionCategory::WindowActivated,
+                    ) {
+                        right_clicked = (info.action_type == MouseButton::Right.click_action_type() && info.menu.is_none())
+                            .then(|| ElementSummary::from(info));
+                    }
+                }
+                let elapsed = started.elapsed();
+                counters.resolve_time.record(elapsed);
+                tracing::debug!(resolve_ms = elapsed.as_secs_f64() * 1000.0, resolved = resolved.is_some(), "Resolved step");
+                match resolved {
+                    Some((info, frame)) => EmitJob::Step(Box::new(info), frame, captured_at),
+                    None => {
+                        counters.unresolved.fetch_add(1, Ordering::Relaxed);
+                        tracking_state.step_finished();
+                        continue;
+                    }
+                }
+            }
+            PipelineJob::DiscardLastStep => EmitJob::DiscardLastStep,
+        };
+
+        // Blocking here is fine; the capture stage sees it as backpressure
+        // once the resolve queue fills up too
+        enqueued(&counters.emit_queue_depth, &counters.emit_queue_peak);
+        let emit_job = match sender.try_send(emit_job) {
+            Ok(()) => continue,
+            Err(TrySendError::Full(emit_job)) => emit_job,
+            Err(TrySendError::Disconnected(_)) => return,
+        };
+        counters.backpressure_waits.fetch_add(1, Ordering::Relaxed);
+        if sender.send(emit_job).is_err() {
+            return;
+        }
+    }
+}
+
+/// Describes an element that has no input position of its own (keyboard
+/// and navigation steps), placed at its center.
+fn centered_element_info(automation: &Automation, element: &CapturedElement, fallback_point: POINT) -> ElementInfo {
+    let (x, y) = element.element().bounds()
+        .map(|bounds| bounds.center())
+        .unwrap_or((fallback_point.x, fallback_point.y));
+    build_element_info(automation, element.element(), x, y)
+}
+
+/// Describes the control a pointer step at `x`, `y` was meant for, from the
+/// element that was under the pointer.
+fn pointer_element_info(automation: &Automation, element: &CapturedElement, x: i32, y: i32) -> ElementInfo {
+    let target = interactive_element(automation, element.element());
+    build_element_info(automation, &target, x, y)
+}
+
+/// Fills in the reconstructed text where the control reports no value.
+/// Returns false if there is still nothing to show. Text from a password
+/// run is never used, whatever the element says about itself: the step is
+/// marked as a password step so `redact_step` hides it.
+fn apply_typed_text(element_info: &mut ElementInfo, text: String, password_run: bool) -> bool {
+    if password_run || element_info.is_password {
+        element_info.is_password = true;
+        return true;
+    }
+    // Prefer what the control reports; our reconstruction ignores caret
+    // moves, selection and non-US layouts
+    if element_info.value.is_empty() {
+        element_info.value = text;
+    }
+    !element_info.value.is_empty()
+}
+
+/// Hides what a password field step would give away, per `config`.
+fn redact_step(element_info: &mut ElementInfo, frame: Option<&mut RawFrame>, config: &RedactionConfig) {
+    if !element_info.is_password {
+        return;
+    }
+    element_info.value = match config.password_value {
+        PasswordRedaction::Drop => String::new(),
+        PasswordRedaction::Mask => PASSWORD_MASK.to_string(),
+    };
+    if config.blur_screenshot {
+        if let (Some(frame), Some(region)) = (frame, element_info.screenshot_bounds.as_ref()) {
+            frame.blur_region(region);
+        }
+    }
+    element_info.redacted = true;
+}
+
+/// Records where a clicked menu item sits. If the element under the click
+/// isn't the item (the menu was already closing, or doesn't expose its
+/// items there), the item is described from what was read while it was
+/// open.
+fn attach_menu(element_info: &mut ElementInfo, menu: CapturedMenu, right_clicked: Option<&ElementSummary>) {
+    let CapturedMenu { path, item_bounds, context_menu } = menu;
+    if element_info.control_type != "MenuItem" {
+        element_info.name = path.last().cloned().unwrap_or_default();
+        element_info.control_type = "MenuItem".to_string();
+        element_info.automation_id.clear();
+        element_info.class_name.clear();
+        element_info.parent_name = path.iter().rev().nth(1).cloned().unwrap_or_default();
+        element_info.ancestors.clear();
+        element_info.value.clear();
+        element_info.state.clear();
+        element_info.help_text.clear();
+        element_info.is_password = false;
+        element_info.bounds = Some(item_bounds);
+        element_info.selector = None;
+    }
+    element_info.menu = Some(MenuPath {
+        items: path,
+        origin: right_clicked.filter(|_| context_menu).cloned(),
+    });
+}
+
+fn resolve_step(
+    automation: &Automation,
+    step: CapturedStep,
+    redaction: &RedactionConfig,
+    right_clicked: Option<&ElementSummary>,
+) -> Option<(ElementInfo, Option<RawFrame>)> {
+    let CapturedStep { request, element, mut frame, fallback_point, timestamp, out_of_scope, .. } = step;
+    let element = element.as_ref();
+
+    let mut element_info = match request {
+        StepRequest::Click { point, button, menu } => {
+            let mut element_info = pointer_element_info(automation, element?, point.x, point.y);
+            element_info.action_category = ActionCategory::Click;
+            element_info.action_type = button.click_action_type().to_string();
+            if let Some(menu) = menu {
+                attach_menu(&mut element_info, menu, right_clicked);
+            }
+            element_info
+        }
+        StepRequest::DoubleClick { point } => {
+            let mut element_info = pointer_element_info(automation, element?, point.x, point.y);
+            element_info.action_category = ActionCategory::DoubleClick;
+            element_info.action_type = "double_click".to_string();
+            element_info
+        }
+        StepRequest::Drag { button, start, end, path, drop_target } => {
+            let mut element_info = pointer_element_info(automation, element?, start.0, start.1);
+            let drop_target = drop_target
+                .map(|target| ElementSummary::from(&pointer_element_info(automation, &target, end.0, end.1)));
+            element_info.action_category = ActionCategory::Drag;
+            element_info.action_type = button.drag_action_type().to_string();
+            element_info.drag = Some(DragDetails {
+                end_x: end.0,
+                end_y: end.1,
+                path,
+                drop_target,
+            });
+            element_info
+        }
+        StepRequest::Scroll { point, direction, amount } => {
+            let container = scroll_container(automation, element?.element());
+            let mut element_info = build_element_info(automation, &container, point.x, point.y);
+            element_info.action_category = ActionCategory::Scroll;
+            element_info.action_type = "scroll".to_string();
+            element_info.scroll = Some(ScrollDetails { direction, amount });
+            element_info
+        }
+        StepRequest::Keystroke { action_type } => {
+            let mut element_info = centered_element_info(automation, element?, fallback_point);
+            element_info.action_category = ActionCategory::Keystroke;
+            element_info.action_type = action_type.to_string();
+            element_info
+        }
+        StepRequest::TextInput { text, password } => {
+            let mut element_info = centered_element_info(automation, element?, fallback_point);
+            if !apply_typed_text(&mut element_info, text, password) {
+                return None;
+            }
+            element_info.action_category = ActionCategory::TextInput;
+            element_info.action_type = "type".to_string();
+            element_info
+        }
+        StepRequest::Shortcut { combo } => {
+            let mut element_info = centered_element_info(automation, element?, fallback_point);
+            element_info.action_category = ActionCategory::Shortcut;
+            element_info.action_type = "shortcut".to_string();
+            element_info.key_combo = Some(combo);
+            element_info
+        }
+        StepRequest::Hover { point, tooltip } => {
+            let mut element_info = pointer_element_info(automation, element?, point.x, point.y);
+            element_info.action_category = ActionCategory::Hover;
+            element_info.action_type = "hover".to_string();
+            element_info.tooltip = tooltip;
+            element_info
+        }
+        StepRequest::FocusChanged => {
+            let mut element_info = centered_element_info(automation, element?, fallback_point);
+            element_info.action_category = ActionCategory::FocusChanged;
+            element_info.action_type = "focus".to_string();
+            element_info
+        }
+        StepRequest::WindowActivated => {
+            let mut element_info = centered_element_info(automation, element?, fallback_point);
+            element_info.action_category = ActionCategory::WindowActivated;
+            element_info.action_type = "activate_window".to_string();
+            element_info
+        }
+        StepRequest::Manual => ElementInfo {
+            x: None,  // No position for manual screenshot
+            y: None,  // No position for manual screenshot
+            screen_context: ScreenContext::new(),  // Using ScreenContext struct
+            name: "Manual Screenshot".to_string(),
+            control_type: "Screenshot".to_string(),
+            automation_id: String::new(),
+            class_name: String::new(),
+            window_title: "Manual Capture".to_string(),
+            app: None,
+            parent_name: String::new(),
+            ancestors: Vec::new(),
+            action_type: "capture".to_string(),
+            action_category: ActionCategory::Manual,
+            timestamp: String::new(),
+            screenshot: None,
+            value: String::new(),
+            state: String::new(),
+            help_text: String::new(),
+            is_password: false,
+            redacted: false,
+            key_combo: None,
+            drag: None,
+            scroll: None,
+            menu: None,
+            bounds: None,
+            screenshot_bounds: None,
+            tooltip: None,
+            selector: None,
+            session_id: None,
+            sequence: None,
+            out_of_scope: false,
+        },
+    };
+
+    // When the user acted, not when we got round to it
+    element_info.timestamp = timestamp;
+    element_info.out_of_scope = out_of_scope;
+    element_info.screenshot_bounds = frame.as_ref()
+        .zip(element_info.bounds.as_ref())
+        .and_then(|(frame, bounds)| frame.image_rect(bounds));
+    redact_step(&mut element_info, frame.as_mut(), redaction);
+    Some((element_info, frame))
+}
+
+fn run_emit_stage(
+    app_handle: AppHandle,
+    receiver: Receiver<EmitJob>,
+    metrics: PipelineMetrics,
+    tracking_state: TrackingState,
+) {
+    let counters = &metrics.counters;
+    for job in receiver {
+        dequeued(&counters.emit_queue_depth);
+        let result = match job {
+            EmitJob::Step(mut info, frame, captured_at) => {
+                info.screenshot = frame.as_ref().and_then(encode_frame);
+                match emit_step(&app_handle, &tracking_state, *info) {
+                    Ok(true) => {
+                        counters.emitted.fetch_add(1, Ordering::Relaxed);
+                        counters.latency.record(captured_at.elapsed());
+                        Ok(())
+                    }
+                    Ok(false) => {
+                        counters.late.fetch_add(1, Ordering::Relaxed);
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            EmitJob::DiscardLastStep => discard_last_step(&app_handle, &tracking_state),
+        };
+        tracking_state.step_finished();
+
+        if let Err(e) = result {
+            tracing::error!(error = %e, "Error emitting step");
+        }
+    }
+}
+
+/// Stamps, journals and emits a step. Returns false if the step was dropped
+/// because its recording already ended.
+fn emit_step(app_handle: &AppHandle, tracking_state: &TrackingState, mut info: ElementInfo) -> Result<bool, Error> {
+    // Numbered at emit time so sequence order matches what the frontend sees
+    let stamp = tracking_state.with_session(|session| {
+        if session.limit_reached() {
+            return None;
+        }
+        let sequence = session.next_sequence();
+        Some((session.id.clone(), sequence, session.progress()))
+    }).flatten();
+    let (session_id, sequence, progress) = match stamp {
+        Some(stamp) => stamp,
+        None => {
+            tracing::info!(action = %info.action_type, "Dropping step that finished after its recording ended");
+            return Ok(false);
+        }
+    };
+    info.session_id = Some(session_id.clone());
+    info.sequence = Some(sequence);
+
+    // Persist before the frontend sees it, so a crash can't lose a shown step
+    if let Err(e) = journal::append_step(&info) {
+        tracing::error!(session_id = %session_id, sequence, error = %e, "Failed to journal step");
+    }
+
+    tracing::info!(
+        session_id = %session_id,
+        sequence,
+        action = %info.action_type,
+        control_type = %info.control_type,
+        x = ?info.x,
+        y = ?info.y,
+        "Emitting step"
+    );
+    app_handle.emit_all("element_interaction", info)
+        .map_err(|e| Error::EmitError(e.to_string()))?;
+
+    emit_progress(app_handle, &progress)?;
+    match progress.remaining {
+        Some(0) => {
+            app_handle.emit_all("recording-limit-reached", &progress)
+                .map_err(|e| Error::EmitError(e.to_string()))?;
+            stop_at_limit(app_handle, tracking_state, &progress);
+        }
+        Some(remaining) if remaining <= STEP_LIMIT_WARNING_REMAINING => {
+            app_handle.emit_all("recording-limit-near", &progress)
+                .map_err(|e| Error::EmitError(e.to_string()))?;
+        }
+        _ => {}
+    }
+    Ok(true)
+}
+
+fn emit_progress(app_handle: &AppHandle, progress: &StepProgress) -> Result<(), Error> {
+    app_handle.emit_all("recording-progress", progress)
+        .map_err(|e| Error::EmitError(e.to_string()))
+}
+
+fn stop_at_limit(app_handle: &AppHandle, tracking_state: &TrackingState, progress: &StepProgress) {
+    tracing::info!(session_id = %progress.session_id, limit = ?progress.limit, "Step limit reached, stopping recording");
+    // Nothing new gets captured from here. Stopping waits for this thread to
+    // drain the pipeline, so the rest happens on another one.
+    tracking_state.set_state(STATE_STOPPED);
+
+    let app_handle = app_handle.clone();
+    let tracking_state = tracking_state.clone();
+    std::thread::spawn(move || {
+        let window = match app_handle.get_window("main") {
+            Some(window) => window,
+            None => {
+                tracing::error!("Main window not found, can't finish recording");
+                return;
+            }
+        };
+        let window_state = app_handle.state::<WindowState>();
+        if let Err(e) = finish_recording(&window, &tracking_state, &window_state) {
+            tracing::error!(error = %e, "Failed to finish recording at step limit");
+        }
+    });
+}
+
+fn discard_last_step(app_handle: &AppHandle, tracking_state: &TrackingState) -> Result<(), Error> {
+    let discarded = match tracking_state.with_session(|session| session.discard_last()).flatten() {
+        Some(discarded) => discarded,
+        None => return Ok(()),
+    };
+
+    if let Err(e) = journal::append_discard(&discarded.session_id, discarded.sequence) {
+        tracing::error!(session_id = %discarded.session_id, sequence = discarded.sequence, error = %e, "Failed to journal discarded step");
+    }
+    app_handle.emit_all("step-discarded", discarded)
+        .map_err(|e| Error::EmitError(e.to_string()))?;
+
+    if let Some(progress) = tracking_state.with_session(|session| session.progress()) {
+        emit_progress(app_handle, &progress)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_automation::backend::AccessibilityBackend;
+    use crate::ui_automation::element::get_element_info;
+    use crate::ui_automation::mock::MockBackend;
+
+    // A sign-in form right after the password was typed and Tab pressed:
+    // focus has moved on to the button
+    const SIGN_IN_FORM: &str = r#"{
+        "root": { "control_type": "Pane", "name": "Desktop", "children": [
+            { "control_type": "Window", "name": "Sign in", "class_name": "SignInDialog",
+              "window": { "hwnd": 7, "process_name": "client.exe" },
+              "bounds": { "x": 0, "y": 0, "width": 400, "height": 300 },
+              "children": [
+                { "control_type": "Edit", "name": "User name", "value": "alice", "value_pattern": true,
+                  "focusable": true, "bounds": { "x": 20, "y": 60, "width": 200, "height": 24 } },
+                { "control_type": "Edit", "name": "Password", "password": true, "value_pattern": true,
+                  "focusable": true, "bounds": { "x": 20, "y": 100, "width": 200, "height": 24 } },
+                { "control_type": "Button", "name": "Sign in", "focusable": true, "focused": true,
+                  "bounds": { "x": 20, "y": 140, "width": 80, "height": 24 } }
+              ] }
+        ] }
+    }"#;
+
+    fn now_focused(backend: &MockBackend) -> ElementInfo {
+        let focused = backend.focused_element().unwrap();
+        build_element_info(backend, &focused, 60, 152)
+    }
+
+    #[test]
+    fn password_run_stays_redacted_after_focus_moves_on() {
+        let backend = MockBackend::from_json(SIGN_IN_FORM).unwrap();
+        let mut info = now_focused(&backend);
+        assert_eq!(info.name, "Sign in");
+        assert!(!info.is_password);
+
+        assert!(apply_typed_text(&mut info, "hunter2".to_string(), true));
+        redact_step(&mut info, None, &RedactionConfig::default());
+        assert_eq!(info.value, PASSWORD_MASK);
+        assert!(info.is_password);
+        assert!(info.redacted);
+    }
+
+    #[test]
+    fn password_run_value_can_be_dropped() {
+        let backend = MockBackend::from_json(SIGN_IN_FORM).unwrap();
+        let mut info = now_focused(&backend);
+        let config = RedactionConfig { password_value: PasswordRedaction::Drop, ..Default::default() };
+
+        assert!(apply_typed_text(&mut info, "hunter2".to_string(), true));
+        redact_step(&mut info, None, &config);
+        assert_eq!(info.value, "");
+        assert!(info.redacted);
+    }
+
+    #[test]
+    fn plain_run_falls_back_to_typed_text() {
+        let backend = MockBackend::from_json(SIGN_IN_FORM).unwrap();
+        let mut info = now_focused(&backend);
+
+        assert!(apply_typed_text(&mut info, "hello".to_string(), false));
+        redact_step(&mut info, None, &RedactionConfig::default());
+        assert_eq!(info.value, "hello");
+        assert!(!info.redacted);
+
+        assert!(!apply_typed_text(&mut now_focused(&backend), String::new(), false));
+    }
+
+    #[test]
+    fn control_value_wins_over_typed_text() {
+        let backend = MockBackend::from_json(SIGN_IN_FORM).unwrap();
+        let mut info = get_element_info(&backend, 30, 70).unwrap();
+        assert_eq!(info.name, "User name");
+
+        assert!(apply_typed_text(&mut info, "alcie".to_string(), false));
+        assert_eq!(info.value, "alice");
+    }
+}
@@ -0,0 +1,207 @@
+use std::cell::RefCell;
+use std::sync::mpsc::{self, Sender};
+use std::time::Instant;
+use windows::Win32::Foundation::{HMODULE, HWND, LPARAM, WPARAM};
+use windows::Win32::System::Com::{CoInitializeEx, COINIT_MULTITHREADED};
+use windows::Win32::System::Threading::{GetCurrentProcessId, GetCurrentThreadId};
+use windows::Win32::UI::Accessibility::{IUIAutomationElement, SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
+use windows::Win32::UI::WindowsAndMessaging::{
+    DispatchMessageW, GetMessageW, GetWindowThreadProcessId, PostThreadMessageW, TranslateMessage, CHILDID_SELF, EVENT_OBJECT_FOCUS,
+    EVENT_SYSTEM_FOREGROUND, EVENT_SYSTEM_MENUPOPUPEND, EVENT_SYSTEM_MENUPOPUPSTART, MSG, OBJID_CLIENT,
+    WINEVENT_OUTOFCONTEXT, WINEVENT_SKIPOWNPROCESS, WM_QUIT,
+};
+
+use crate::models::error::Error;
+use crate::ui_automation::automation::{Automation, CapturedElement};
+use crate::ui_automation::backend::AccessibilityBackend;
+use crate::ui_automation::menu::snapshot_menu;
+use super::menus::OpenMenus;
+
+/// Changes in other apps' UI that aren't input but can make a step.
+/// Elements are read as the event arrives, before anything else changes.
+#[derive(Debug, Clone)]
+pub enum UiEvent {
+    /// Keyboard focus moved to `element`; `hwnd` is the window it's in
+    FocusChanged { hwnd: isize, element: CapturedElement, time: Instant },
+    /// A different top-level window came to the front
+    WindowActivated { hwnd: isize, element: Option<CapturedElement>, time: Instant },
+    /// One of the recorder's own windows came to the front. Never a step,
+    /// but switching back from it to the previous app is a window switch.
+    OwnWindowActivated { hwnd: isize },
+}
+
+struct Watcher {
+    automation: Automation,
+    menus: OpenMenus,
+    sender: Sender<UiEvent>,
+}
+
+thread_local! {
+    // WinEvent callbacks are plain functions, called on the watcher thread
+    static WATCHER: RefCell<Option<Watcher>> = const { RefCell::new(None) };
+}
+
+/// The element a focus event is about. Plain Win32 controls report their
+/// own window; other frameworks report a part of their window, which UI
+/// Automation knows as the focused element.
+fn focus_target(automation: &Automation, hwnd: HWND, id_object: i32, id_child: i32) -> Option<IUIAutomationElement> {
+    if id_object == OBJID_CLIENT.0 && id_child == CHILDID_SELF as i32 {
+        automation.element_from_handle(hwnd.0)
+    } else {
+        automation.focused_element()
+    }
+}
+
+fn is_own_window(hwnd: HWND) -> bool {
+    let mut process_id = 0;
+    unsafe {
+        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
+        process_id == GetCurrentProcessId()
+    }
+}
+
+unsafe extern "system" fn win_event_proc(
+    _hook: HWINEVENTHOOK,
+    event: u32,
+    hwnd: HWND,
+    id_object: i32,
+    id_child: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    WATCHER.with(|watcher| {
+        let watcher = watcher.borrow();
+        let Some(Watcher { automation, menus, sender }) = watcher.as_ref() else {
+            return;
+        };
+        let time = Instant::now();
+        match event {
+            EVENT_SYSTEM_MENUPOPUPSTART => {
+                let menu = automation.element_from_handle(hwnd.0)
+                    .and_then(|element| snapshot_menu(automation, &element, hwnd.0));
+                if let Some(menu) = menu {
+                    tracing::debug!(items = menu.items.len(), opener = ?menu.opener.as_ref().map(|item| &item.name), "Menu opened");
+                    menus.opened(menu);
+                }
+            }
+            EVENT_SYSTEM_MENUPOPUPEND => menus.closed(hwnd.0),
+            EVENT_SYSTEM_FOREGROUND if is_own_window(hwnd) => {
+                let _ = sender.send(UiEvent::OwnWindowActivated { hwnd: hwnd.0 });
+            }
+            EVENT_SYSTEM_FOREGROUND => {
+                let element = automation.element_from_handle(hwnd.0).map(CapturedElement::new);
+                let _ = sender.send(UiEvent::WindowActivated { hwnd: hwnd.0, element, time });
+            }
+            EVENT_OBJECT_FOCUS => {
+                if let Some(element) = focus_target(automation, hwnd, id_object, id_child) {
+                    let element = CapturedElement::new(element);
+                    let _ = sender.send(UiEvent::FocusChanged { hwnd: hwnd.0, element, time });
+                }
+            }
+            _ => {}
+        }
+    });
+}
+
+/// Follows focus, foreground windows and menus in other apps through
+/// WinEvents, on a dedicated thread with its own UI Automation client.
+/// Menus are tracked in `OpenMenus`; everything else arrives through
+/// `next_event`. Dropping the watcher ends the thread.
+///
+/// UI Automation's own events (`IUIAutomationFocusChangedEventHandler` and
+/// the menu opened handlers) need COM objects the `windows` crate can only
+/// define with `windows-implement`, which isn't a dependency. WinEvents need
+/// just a callback, and the element is still read through UI Automation as
+/// each event arrives.
+pub struct UiEventWatcher {
+    receiver: mpsc::Receiver<UiEvent>,
+    thread_id: u32,
+}
+
+impl UiEventWatcher {
+    pub fn start(menus: OpenMenus) -> Result<Self, Error> {
+        let (sender, receiver) = mpsc::channel();
+        let (ready_tx, ready_rx) = mpsc::channel();
+        std::thread::spawn(move || unsafe {
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            let automation = match Automation::new() {
+                Ok(automation) => automation,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(Error::from(e)));
+                    return;
+                }
+            };
+            WATCHER.with(|watcher| *watcher.borrow_mut() = Some(Watcher { automation, menus, sender }));
+
+            let flags = WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS;
+            // Our own windows coming to the front are needed too, to notice
+            // switching away from an app and back
+            let foreground_hook = SetWinEventHook(
+                EVENT_SYSTEM_FOREGROUND,
+                EVENT_SYSTEM_FOREGROUND,
+                HMODULE::default(),
+                Some(win_event_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT,
+            );
+            let menu_hook = SetWinEventHook(
+                EVENT_SYSTEM_MENUPOPUPSTART,
+                EVENT_SYSTEM_MENUPOPUPEND,
+                HMODULE::default(),
+                Some(win_event_proc),
+                0,
+                0,
+                flags,
+            );
+            let focus_hook = SetWinEventHook(
+                EVENT_OBJECT_FOCUS,
+                EVENT_OBJECT_FOCUS,
+                HMODULE::default(),
+                Some(win_event_proc),
+                0,
+                0,
+                flags,
+            );
+            if foreground_hook.is_invalid() || menu_hook.is_invalid() || focus_hook.is_invalid() {
+                UnhookWinEvent(foreground_hook);
+                UnhookWinEvent(menu_hook);
+                UnhookWinEvent(focus_hook);
+                let _ = ready_tx.send(Err(Error::InputError("Failed to watch UI events".to_string())));
+                return;
+            }
+            let _ = ready_tx.send(Ok(GetCurrentThreadId()));
+
+            // Out-of-context events are delivered while this thread pumps messages
+            let mut msg = MSG::default();
+            while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+
+            UnhookWinEvent(focus_hook);
+            UnhookWinEvent(menu_hook);
+            UnhookWinEvent(foreground_hook);
+            WATCHER.with(|watcher| *watcher.borrow_mut() = None);
+        });
+
+        let thread_id = ready_rx
+            .recv()
+            .map_err(|e| Error::InputError(e.to_string()))??;
+
+        Ok(Self { receiver, thread_id })
+    }
+
+    /// The next event already waiting, without blocking.
+    pub fn next_event(&self) -> Option<UiEvent> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for UiEventWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use std::time::Duration;
+use windows::core::{ComInterface, Result as WindowsResult};
+use windows::Win32::Foundation::{HWND, POINT};
+use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL, VARENUM, VT_BOOL, VT_I4};
+use windows::Win32::UI::Accessibility::*;
+use crate::models::element_info::{AppContext, BoundingRect};
+use crate::models::screen::ScreenContext;
+use super::backend::{AccessibilityBackend, AccessibleNode};
+use super::process::{process_image_path, process_name_from_path};
+use super::utils::{variant_from_i32, variant_to_string};
+use super::window::{app_context, element_window_context, foreground_window_title, WindowContext};
+
+// Everything element resolution, selectors and window lookup read. Pattern
+// support is fetched through the availability properties, which answers
+// "is this a text field" without marshalling the pattern objects.
+const CACHED_PROPERTIES: &[UIA_PROPERTY_ID] = &[
+    UIA_NamePropertyId,
+    UIA_ControlTypePropertyId,
+    UIA_AutomationIdPropertyId,
+    UIA_ClassNamePropertyId,
+    UIA_HelpTextPropertyId,
+    UIA_ValueValuePropertyId,
+    UIA_IsEnabledPropertyId,
+    UIA_IsOffscreenPropertyId,
+    UIA_IsPasswordPropertyId,
+    UIA_IsKeyboardFocusablePropertyId,
+    UIA_HasKeyboardFocusPropertyId,
+    UIA_SelectionItemIsSelectedPropertyId,
+    UIA_ToggleToggleStatePropertyId,
+    UIA_BoundingRectanglePropertyId,
+    UIA_NativeWindowHandlePropertyId,
+    UIA_ProcessIdPropertyId,
+    UIA_IsValuePatternAvailablePropertyId,
+    UIA_IsTextPatternAvailablePropertyId,
+    UIA_IsScrollPatternAvailablePropertyId,
+];
+
+/// One UI Automation client per thread that resolves elements. Every
+/// element it hands out comes with `CACHED_PROPERTIES` prefetched, so
+/// reading them costs no further cross-process calls; use the `Cached*`
+/// getters (or the helpers below) on them, not `Current*`.
+///
+/// COM must already be initialized on the thread, and the instance must
+/// stay on it.
+pub struct Automation {
+    client: IUIAutomation,
+    tree_walker: IUIAutomationTreeWalker,
+    cache_request: IUIAutomationCacheRequest,
+}
+
+impl Automation {
+    pub fn new() -> WindowsResult<Self> {
+        unsafe {
+            let client: IUIAutomation = CoCreateInstance(&CUIAutomation, None, CLSCTX_ALL)?;
+            let tree_walker = client.ControlViewWalker()?;
+            let cache_request = client.CreateCacheRequest()?;
+            for &property in CACHED_PROPERTIES {
+                cache_request.AddProperty(property)?;
+            }
+            Ok(Self { client, tree_walker, cache_request })
+        }
+    }
+
+    /// Gives up on an app that doesn't answer within `timeout`, rather than
+    /// after UI Automation's defaults of several seconds. Clients older than
+    /// Windows 8 keep the defaults.
+    pub fn set_timeout(&self, timeout: Duration) {
+        let millis = timeout.as_millis() as u32;
+        if let Ok(client) = self.client.cast::<IUIAutomation2>() {
+            unsafe {
+                let _ = client.SetConnectionTimeout(millis);
+                let _ = client.SetTransactionTimeout(millis);
+            }
+        }
+    }
+
+    fn find_all_of_type(&self, element: &IUIAutomationElement, scope: TreeScope, control_type: i32) -> Vec<IUIAutomationElement> {
+        unsafe {
+            let found = self.client.CreatePropertyCondition(UIA_ControlTypePropertyId, variant_from_i32(control_type))
+                .and_then(|of_type| self.client.CreateAndCondition(&self.client.ControlViewCondition()?, &of_type))
+                .and_then(|condition| element.FindAllBuildCache(scope, &condition, &self.cache_request));
+            let Ok(found) = found else {
+                return Vec::new();
+            };
+            (0..found.Length().unwrap_or(0))
+                .filter_map(|index| found.GetElement(index).ok())
+                .collect()
+        }
+    }
+
+    pub fn element_from_handle(&self, hwnd: isize) -> Option<IUIAutomationElement> {
+        unsafe { self.client.ElementFromHandleBuildCache(HWND(hwnd), &self.cache_request).ok() }
+    }
+}
+
+/// An element looked up on the capture thread, on its way to the thread
+/// that describes it. Its cached properties are what they were when the
+/// input happened. It's the raw element under the pointer or with focus;
+/// finding the control it belongs to is left to the resolve worker.
+#[derive(Debug, Clone)]
+pub struct CapturedElement(IUIAutomationElement);
+
+// UI Automation client objects are free-threaded, and every thread that
+// looks up or reads elements here is in the multithreaded apartment
+unsafe impl Send for CapturedElement {}
+
+impl CapturedElement {
+    pub fn new(element: IUIAutomationElement) -> Self {
+        Self(element)
+    }
+
+    pub fn element(&self) -> &IUIAutomationElement {
+        &self.0
+    }
+}
+
+impl AccessibilityBackend for Automation {
+    type Node = IUIAutomationElement;
+
+    fn element_from_point(&self, x: i32, y: i32) -> Option<Self::Node> {
+        unsafe { self.client.ElementFromPointBuildCache(POINT { x, y }, &self.cache_request).ok() }
+    }
+
+    fn focused_element(&self) -> Option<Self::Node> {
+        unsafe { self.client.GetFocusedElementBuildCache(&self.cache_request).ok() }
+    }
+
+    fn root(&self) -> Option<Self::Node> {
+        unsafe { self.client.GetRootElementBuildCache(&self.cache_request).ok() }
+    }
+
+    /// Control-view parent
+    fn parent(&self, element: &Self::Node) -> Option<Self::Node> {
+        unsafe { self.tree_walker.GetParentElementBuildCache(element, &self.cache_request).ok() }
+    }
+
+    fn children(&self, element: &Self::Node) -> Vec<Self::Node> {
+        let mut children = Vec::new();
+        unsafe {
+            let mut current = self.tree_walker.GetFirstChildElementBuildCache(element, &self.cache_request).ok();
+            while let Some(child) = current {
+                current = self.tree_walker.GetNextSiblingElementBuildCache(&child, &self.cache_request).ok();
+                children.push(child);
+            }
+        }
+        children
+    }
+
+    /// One FindAll call instead of a walker call per sibling. Limited to
+    /// the control view, like the walker.
+    fn children_of_type(&self, element: &Self::Node, control_type: i32) -> Vec<Self::Node> {
+        self.find_all_of_type(element, TreeScope_Children, control_type)
+    }
+
+    /// One FindAll call over the whole subtree, in the control view.
+    fn descendants_of_type(&self, element: &Self::Node, control_type: i32) -> Vec<Self::Node> {
+        self.find_all_of_type(element, TreeScope_Descendants, control_type)
+    }
+
+    fn is_same_element(&self, a: &Self::Node, b: &Self::Node) -> bool {
+        unsafe { self.client.CompareElements(a, b).is_ok_and(|same| same.as_bool()) }
+    }
+
+    fn window_of(&self, element: &Self::Node) -> Option<WindowContext> {
+        element_window_context(self, element)
+    }
+
+    fn process_name(&self, element: &Self::Node) -> String {
+        cached_i32(element, UIA_ProcessIdPropertyId)
+            .and_then(|process_id| process_image_path(process_id as u32))
+            .map(|path| process_name_from_path(&path))
+            .unwrap_or_default()
+    }
+
+    fn app_context(&self, window: WindowContext) -> AppContext {
+        app_context(window)
+    }
+
+    fn foreground_window_title(&self) -> Option<String> {
+        foreground_window_title()
+    }
+
+    fn screen_context(&self) -> ScreenContext {
+        ScreenContext::new()
+    }
+}
+
+impl AccessibleNode for IUIAutomationElement {
+    fn control_type_id(&self) -> Option<i32> {
+        cached_i32(self, UIA_ControlTypePropertyId)
+    }
+
+    fn name(&self) -> String {
+        cached_string(self, UIA_NamePropertyId)
+    }
+
+    fn automation_id(&self) -> String {
+        cached_string(self, UIA_AutomationIdPropertyId)
+    }
+
+    fn class_name(&self) -> String {
+        cached_string(self, UIA_ClassNamePropertyId)
+    }
+
+    fn help_text(&self) -> String {
+        cached_string(self, UIA_HelpTextPropertyId)
+    }
+
+    fn value(&self) -> String {
+        cached_string(self, UIA_ValueValuePropertyId)
+    }
+
+    fn is_enabled(&self) -> bool {
+        // Providers that don't report it are treated as enabled
+        !unsafe { self.CachedIsEnabled() }.is_ok_and(|enabled| !enabled.as_bool())
+    }
+
+    fn is_selected(&self) -> bool {
+        cached_bool(self, UIA_SelectionItemIsSelectedPropertyId)
+    }
+
+    fn toggle_state(&self) -> Option<i32> {
+        cached_i32(self, UIA_ToggleToggleStatePropertyId)
+    }
+
+    fn is_offscreen(&self) -> bool {
+        cached_bool(self, UIA_IsOffscreenPropertyId)
+    }
+
+    fn is_password(&self) -> bool {
+        cached_bool(self, UIA_IsPasswordPropertyId)
+    }
+
+    fn is_focusable(&self) -> bool {
+        cached_bool(self, UIA_IsKeyboardFocusablePropertyId)
+    }
+
+    fn has_focus(&self) -> bool {
+        cached_bool(self, UIA_HasKeyboardFocusPropertyId)
+    }
+
+    fn supports_value(&self) -> bool {
+        cached_bool(self, UIA_IsValuePatternAvailablePropertyId)
+    }
+
+    fn supports_text(&self) -> bool {
+        cached_bool(self, UIA_IsTextPatternAvailablePropertyId)
+    }
+
+    fn supports_scroll(&self) -> bool {
+        cached_bool(self, UIA_IsScrollPatternAvailablePropertyId)
+    }
+
+    fn bounds(&self) -> Option<BoundingRect> {
+        let rect = unsafe { self.CachedBoundingRectangle() }.ok()?;
+        let bounds = BoundingRect {
+            x: rect.left,
+            y: rect.top,
+            width: rect.right - rect.left,
+            height: rect.bottom - rect.top,
+        };
+        (bounds.width > 0 && bounds.height > 0).then_some(bounds)
+    }
+}
+
+fn cached_string(element: &IUIAutomationElement, property: UIA_PROPERTY_ID) -> String {
+    unsafe {
+        element.GetCachedPropertyValue(property)
+            .map(variant_to_string)
+            .unwrap_or_default()
+    }
+}
+
+fn cached_bool(element: &IUIAutomationElement, property: UIA_PROPERTY_ID) -> bool {
+    unsafe {
+        match element.GetCachedPropertyValue(property) {
+            Ok(value) if value.Anonymous.Anonymous.vt == VARENUM(VT_BOOL.0) => {
+                value.Anonymous.Anonymous.Anonymous.boolVal.as_bool()
+            }
+            _ => false,
+        }
+    }
+}
+
+fn cached_i32(element: &IUIAutomationElement, property: UIA_PROPERTY_ID) -> Option<i32> {
+    unsafe {
+        match element.GetCachedPropertyValue(property) {
+            Ok(value) if value.Anonymous.Anonymous.vt == VARENUM(VT_I4.0) => {
+                Some(value.Anonymous.Anonymous.Anonymous.lVal)
+            }
+            _ => None,
+        }
+    }
+}
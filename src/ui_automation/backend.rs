@@ -0,0 +1,72 @@
+use crate::models::element_info::{AppContext, BoundingRect};
+use crate::models::screen::ScreenContext;
+use super::window::WindowContext;
+
+/// One element of an accessibility tree. Reads are expected to be cheap
+/// (prefetched or in memory), so callers don't hold on to the results.
+pub trait AccessibleNode: Clone {
+    /// UI Automation control type id, e.g. 50000 for a button; see
+    /// `CONTROL_TYPES`.
+    fn control_type_id(&self) -> Option<i32>;
+    fn name(&self) -> String;
+    fn automation_id(&self) -> String;
+    fn class_name(&self) -> String;
+    fn help_text(&self) -> String;
+    fn value(&self) -> String;
+    fn is_enabled(&self) -> bool;
+    fn is_selected(&self) -> bool;
+    /// 0 off, 1 on, 2 indeterminate; `None` for elements that don't toggle.
+    fn toggle_state(&self) -> Option<i32>;
+    fn is_offscreen(&self) -> bool;
+    /// Password fields, whose value is redacted from steps.
+    fn is_password(&self) -> bool;
+    fn is_focusable(&self) -> bool;
+    fn has_focus(&self) -> bool;
+    fn supports_value(&self) -> bool;
+    fn supports_text(&self) -> bool;
+    fn supports_scroll(&self) -> bool;
+    /// Where the element is on screen; `None` for elements without a
+    /// visible area.
+    fn bounds(&self) -> Option<BoundingRect>;
+}
+
+/// Source of the element tree that steps are resolved against: UI
+/// Automation on Windows, or an in-memory tree (`MockBackend`).
+pub trait AccessibilityBackend {
+    type Node: AccessibleNode;
+
+    fn element_from_point(&self, x: i32, y: i32) -> Option<Self::Node>;
+    fn focused_element(&self) -> Option<Self::Node>;
+    /// The desktop; top-level windows are its children.
+    fn root(&self) -> Option<Self::Node>;
+    /// `None` above the desktop.
+    fn parent(&self, node: &Self::Node) -> Option<Self::Node>;
+    fn children(&self, node: &Self::Node) -> Vec<Self::Node>;
+    /// The children with one control type, in order. Backends that can
+    /// filter on their side should, rather than listing every child.
+    fn children_of_type(&self, node: &Self::Node, control_type: i32) -> Vec<Self::Node> {
+        self.children(node).into_iter()
+            .filter(|child| child.control_type_id() == Some(control_type))
+            .collect()
+    }
+    /// Every node below `node` with one control type, depth first.
+    fn descendants_of_type(&self, node: &Self::Node, control_type: i32) -> Vec<Self::Node> {
+        let mut found = Vec::new();
+        for child in self.children(node) {
+            if child.control_type_id() == Some(control_type) {
+                found.push(child.clone());
+            }
+            found.extend(self.descendants_of_type(&child, control_type));
+        }
+        found
+    }
+    fn is_same_element(&self, a: &Self::Node, b: &Self::Node) -> bool;
+
+    /// The top-level window the node is in.
+    fn window_of(&self, node: &Self::Node) -> Option<WindowContext>;
+    /// Executable name of the process that owns the node, e.g. "excel.exe".
+    fn process_name(&self, node: &Self::Node) -> String;
+    fn app_context(&self, window: WindowContext) -> AppContext;
+    fn foreground_window_title(&self) -> Option<String>;
+    fn screen_context(&self) -> ScreenContext;
+}
@@ -1,277 +1,240 @@
 #![allow(non_upper_case_globals)]
 
-use windows::core::{IUnknown, ComInterface};
-use windows::core::Result as WindowsResult;
 use windows::Win32::UI::Accessibility::*;
-use windows::Win32::System::Com::*;
-use windows::Win32::Foundation::POINT;
-use crate::models::element_info::{ElementInfo, ActionCategory};
-use crate::models::screen::ScreenContext;
-use crate::ui_automation::utils::variant_to_string;
+use crate::models::element_info::{ElementInfo, ActionCategory, AncestorInfo};
+use crate::ui_automation::backend::{AccessibilityBackend, AccessibleNode};
+use crate::ui_automation::selector::build_selector;
 use crate::ui_automation::window::get_window_title_for_element;
-use crate::screenshot::capture_screenshot;
 use chrono::Utc;
 
-pub fn get_element_info(x: i32, y: i32) -> Option<ElementInfo> {
-    unsafe {
-        let automation: IUIAutomation = CoCreateInstance(&CUIAutomation, None, CLSCTX_ALL).ok()?;
-        let element = automation.ElementFromPoint(POINT { x, y }).ok()?;
-
-        // Enhanced element finding logic
-        let element = {
-            // First try the original find_actual_interactive_element
-            let interactive = find_actual_interactive_element(&element).unwrap_or_else(|_| element.clone());
-            
-            // If not interactive, check if it's keyboard interactive (like text fields)
-            if !is_interactive_element(&interactive).unwrap_or(false) {
-                if is_keyboard_interactive(&interactive).unwrap_or(false) {
-                    interactive
-                } else {
-                    // Check parent as last resort (useful when clicking child decorations)
-                    if let Ok(tree_walker) = automation.ControlViewWalker() {
-                        if let Ok(parent) = tree_walker.GetParentElement(&interactive) {
-                            if is_interactive_element(&parent).unwrap_or(false) {
-                                parent
-                            } else {
-                                interactive
-                            }
-                        } else {
-                            interactive
-                        }
-                    } else {
-                        interactive
-                    }
-                }
-            } else {
-                interactive
-            }
-        };
-
-        let mut info = ElementInfo {
-            x: Some(x),
-            y: Some(y),
-            screen_context: ScreenContext::new(),
-            name: String::new(),
-            control_type: String::new(),
-            automation_id: String::new(),
-            class_name: String::new(),
-            window_title: get_window_title_for_element(&element),
-            parent_name: String::new(),
-            action_type: "click".to_string(),
-            action_category: ActionCategory::Click,
-            timestamp: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
-            screenshot: capture_screenshot(x, y),
-            value: String::new(),
-            state: String::new(),
-            help_text: String::new(),
-        };
+pub fn get_element_info<B: AccessibilityBackend>(backend: &B, x: i32, y: i32) -> Option<ElementInfo> {
+    let element = element_at(backend, x, y)?;
+    Some(build_element_info(backend, &element, x, y))
+}
 
-        if let Ok(name) = element.GetCurrentPropertyValue(UIA_NamePropertyId) {
-            info.name = variant_to_string(name);
-        }
+/// The element a click at the point is meant for: the control itself
+/// rather than the text or image inside it.
+pub fn element_at<B: AccessibilityBackend>(backend: &B, x: i32, y: i32) -> Option<B::Node> {
+    let element = backend.element_from_point(x, y)?;
+    Some(interactive_element(backend, &element))
+}
 
-        if let Ok(control_type_variant) = element.GetCurrentPropertyValue(UIA_ControlTypePropertyId) {
-            if control_type_variant.Anonymous.Anonymous.vt == VARENUM(VT_I4.0) {
-                let control_type_id = control_type_variant.Anonymous.Anonymous.Anonymous.lVal;
-                info.control_type = super::CONTROL_TYPES
-                    .iter()
-                    .find(|&&(id, _)| id == control_type_id)
-                    .map(|(_, name)| name.to_string())
-                    .unwrap_or_else(|| format!("Unknown ({})", control_type_id));
+/// The control that `element`, as found under the pointer, belongs to.
+pub fn interactive_element<B: AccessibilityBackend>(backend: &B, element: &B::Node) -> B::Node {
+    // First try the original find_actual_interactive_element
+    let interactive = find_actual_interactive_element(backend, element);
+
+    // If not interactive, check if it's keyboard interactive (like text fields)
+    if !is_interactive_element(&interactive) {
+        if is_keyboard_interactive(&interactive) {
+            interactive
+        } else {
+            // Check parent as last resort (useful when clicking child decorations)
+            match backend.parent(&interactive) {
+                Some(parent) if is_interactive_element(&parent) => parent,
+                _ => interactive,
             }
         }
+    } else {
+        interactive
+    }
+}
 
-        if let Ok(id) = element.GetCurrentPropertyValue(UIA_AutomationIdPropertyId) {
-            info.automation_id = variant_to_string(id);
-        }
-
-        if let Ok(class) = element.GetCurrentPropertyValue(UIA_ClassNamePropertyId) {
-            info.class_name = variant_to_string(class);
-        }
-
-        if let Ok(tree_walker) = automation.ControlViewWalker() {
-            if let Ok(parent) = tree_walker.GetParentElement(&element) {
-                if let Ok(parent_name) = parent.GetCurrentPropertyValue(UIA_NamePropertyId) {
-                    info.parent_name = variant_to_string(parent_name);
-                }
-            }
-        }
+pub fn get_scroll_container_info<B: AccessibilityBackend>(backend: &B, x: i32, y: i32) -> Option<ElementInfo> {
+    let element = scroll_container_at(backend, x, y)?;
+    Some(build_element_info(backend, &element, x, y))
+}
 
-        // Get value
-        if let Ok(value) = element.GetCurrentPropertyValue(UIA_ValueValuePropertyId) {
-            info.value = variant_to_string(value);
-        }
+/// Finds the nearest element at or above the point that can scroll, i.e.
+/// the list, document or pane a wheel event actually moved.
+pub fn scroll_container_at<B: AccessibilityBackend>(backend: &B, x: i32, y: i32) -> Option<B::Node> {
+    let element = backend.element_from_point(x, y)?;
+    Some(scroll_container(backend, &element))
+}
 
-        // Get combined state
-        let mut states = Vec::new();
-        if let Ok(enabled) = element.CurrentIsEnabled() {
-            if !Into::<bool>::into(enabled) { states.push("disabled"); }
-        }
-        if let Ok(selected) = element.GetCurrentPropertyValue(UIA_SelectionItemIsSelectedPropertyId) {
-            if selected.Anonymous.Anonymous.vt == VARENUM(VT_BOOL.0) {
-                if selected.Anonymous.Anonymous.Anonymous.boolVal.as_bool() {
-                    states.push("selected");
-                }
-            }
+/// The nearest element at or above `element` that can scroll.
+pub fn scroll_container<B: AccessibilityBackend>(backend: &B, element: &B::Node) -> B::Node {
+    let mut current = element.clone();
+    loop {
+        if current.supports_scroll() {
+            return current;
         }
-        if let Ok(checked) = element.GetCurrentPropertyValue(UIA_ToggleToggleStatePropertyId) {
-            if checked.Anonymous.Anonymous.vt == VARENUM(VT_I4.0) {
-                match checked.Anonymous.Anonymous.Anonymous.lVal {
-                    1 => states.push("checked"),
-                    2 => states.push("indeterminate"),
-                    _ => {}
-                }
-            }
-        }
-        info.state = states.join(", ");
-
-        // Get help text
-        if let Ok(help) = element.GetCurrentPropertyValue(UIA_HelpTextPropertyId) {
-            info.help_text = variant_to_string(help);
+        match backend.parent(&current) {
+            Some(parent) => current = parent,
+            None => break,
         }
-
-        Some(info)
     }
+
+    // Nothing reports scrolling (common for custom-drawn apps); use what was under the cursor
+    element.clone()
 }
 
-// New helper function
-fn is_keyboard_interactive(element: &IUIAutomationElement) -> WindowsResult<bool> {
-    
-    unsafe {
-        // Check for keyboard usable pattern
-        let value_pattern: Result<IUnknown, _> = element.GetCurrentPattern(UIA_ValuePatternId);
-        if let Ok(unknown) = value_pattern {
-            if unknown.cast::<IUIAutomationValuePattern>().is_ok() {
-                return Ok(true);
-            }
-        }
+/// Text of a tooltip currently shown for `window`, the top-level window
+/// under the pointer. Tooltips aren't children of the element they
+/// describe: some frameworks put them somewhere inside the window, most
+/// show them as top-level popups of their own.
+pub fn find_visible_tooltip<B: AccessibilityBackend>(backend: &B, window: Option<&B::Node>) -> Option<String> {
+    let tooltip_type = UIA_ToolTipControlTypeId.0 as i32;
+    let visible_text = |tooltips: Vec<B::Node>| {
+        tooltips.into_iter()
+            .filter(|tooltip| !tooltip.is_offscreen())
+            .map(|tooltip| tooltip.name())
+            .find(|text| !text.is_empty())
+    };
+    window
+        .and_then(|window| visible_text(backend.descendants_of_type(window, tooltip_type)))
+        .or_else(|| {
+            let root = backend.root()?;
+            visible_text(backend.children_of_type(&root, tooltip_type))
+        })
+}
 
-        // Check for text pattern
-        let text_pattern: Result<IUnknown, _> = element.GetCurrentPattern(UIA_TextPatternId);
-        if let Ok(unknown) = text_pattern {
-            if unknown.cast::<IUIAutomationTextPattern>().is_ok() {
-                return Ok(true);
-            }
-        }
+pub fn control_type_name(control_type_id: i32) -> String {
+    super::CONTROL_TYPES
+        .iter()
+        .find(|&&(id, _)| id == control_type_id)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("Unknown ({})", control_type_id))
+}
 
-        Ok(false)
-    }
+/// The id for a name from `control_type_name`, e.g. 50000 for "Button".
+pub fn control_type_id(name: &str) -> Option<i32> {
+    super::CONTROL_TYPES
+        .iter()
+        .find(|(_, known)| known.eq_ignore_ascii_case(name))
+        .map(|&(id, _)| id)
 }
 
-// Rest of the functions remain exactly the same...
-fn find_actual_interactive_element(element: &IUIAutomationElement) -> WindowsResult<IUIAutomationElement> {
-    // Check if current element is already interactive
-    if is_interactive_element(element)? {
-        return Ok(element.clone());
+pub fn build_element_info<B: AccessibilityBackend>(backend: &B, element: &B::Node, x: i32, y: i32) -> ElementInfo {
+    let window = backend.window_of(element);
+    let mut info = ElementInfo {
+        x: Some(x),
+        y: Some(y),
+        screen_context: backend.screen_context(),
+        name: String::new(),
+        control_type: String::new(),
+        automation_id: String::new(),
+        class_name: String::new(),
+        window_title: get_window_title_for_element(backend, element, window.as_ref()),
+        app: None,
+        parent_name: String::new(),
+        ancestors: Vec::new(),
+        action_type: "click".to_string(),
+        action_category: ActionCategory::Click,
+        timestamp: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        screenshot: None,
+        value: String::new(),
+        state: String::new(),
+        help_text: String::new(),
+        is_password: false,
+        redacted: false,
+        key_combo: None,
+        drag: None,
+        scroll: None,
+        menu: None,
+        bounds: None,
+        screenshot_bounds: None,
+        tooltip: None,
+        selector: None,
+        session_id: None,
+        sequence: None,
+        out_of_scope: false,
+    };
+
+    // Everything below was prefetched with the element
+    info.name = element.name();
+
+    if let Some(control_type_id) = element.control_type_id() {
+        info.control_type = control_type_name(control_type_id);
     }
 
-    // Try to find child interactive elements
-    if let Ok(children) = get_interactive_children(element) {
-        for child in children {
-            if is_interactive_element(&child)? {
-                return Ok(child);
-            }
-        }
+    info.automation_id = element.automation_id();
+    info.class_name = element.class_name();
+
+    if let Some(parent) = backend.parent(element) {
+        info.parent_name = parent.name();
     }
 
-    // If no interactive element found, return original element
-    Ok(element.clone())
-}
+    // Get value
+    info.value = element.value();
+
+    // Get combined state
+    info.state = element_state(element);
 
-pub fn is_interactive_element(element: &IUIAutomationElement) -> WindowsResult<bool> {
-    unsafe {
-        let control_type = match element.CurrentControlType() {
-            Ok(ct) => ct,
-            Err(_) => return Ok(false),
-        };
+    // Get help text
+    info.help_text = element.help_text();
+    info.is_password = element.is_password();
 
-        // Check if it's an interactive control type
-        Ok(matches!(control_type,
-            UIA_ButtonControlTypeId |
-            UIA_ComboBoxControlTypeId |
-            UIA_MenuItemControlTypeId |
-            UIA_TabItemControlTypeId |
-            UIA_ListItemControlTypeId |
-            UIA_TreeItemControlTypeId |
-            UIA_SpinnerControlTypeId |
-            UIA_HyperlinkControlTypeId
-        ))
+    info.app = window.map(|window| backend.app_context(window));
+    info.bounds = element.bounds();
+    // The selector path already walks up to the window; the breadcrumb
+    // is the same levels, nearest first
+    if let Some(selector) = build_selector(backend, element) {
+        info.ancestors = selector.path.iter().rev().skip(1).map(AncestorInfo::from).collect();
+        info.selector = Some(selector);
     }
+
+    info
 }
 
-fn get_interactive_children(element: &IUIAutomationElement) -> WindowsResult<Vec<IUIAutomationElement>> {
-    unsafe {
-        let mut interactive = Vec::new();
-        
-        if let Ok(tree_walker) = initialize_automation()?.ControlViewWalker() {
-            if let Ok(first_child) = tree_walker.GetFirstChildElement(element) {
-                let mut current = Some(first_child);
-                while let Some(child) = current {
-                    if is_interactive_element(&child)? {
-                        interactive.push(child.clone());
-                    }
-                    
-                    current = tree_walker.GetNextSiblingElement(&child).ok();
-                }
-            }
-        }
-        
-        Ok(interactive)
+/// e.g. "disabled, checked"; empty for a plain enabled element.
+pub fn element_state<N: AccessibleNode>(element: &N) -> String {
+    let mut states = Vec::new();
+    if !element.is_enabled() {
+        states.push("disabled");
+    }
+    if element.is_selected() {
+        states.push("selected");
     }
+    match element.toggle_state() {
+        Some(1) => states.push("checked"),
+        Some(2) => states.push("indeterminate"),
+        _ => {}
+    }
+    states.join(", ")
 }
 
-pub fn is_focusable(element: &IUIAutomationElement) -> bool {
-    unsafe {
-        if let Ok(focusable) = element.GetCurrentPropertyValue(UIA_IsKeyboardFocusablePropertyId) {
-            if focusable.Anonymous.Anonymous.vt == VARENUM(VT_BOOL.0) {
-                return focusable.Anonymous.Anonymous.Anonymous.boolVal.as_bool();
-            }
-        }
-        false
-    }
+// New helper function
+fn is_keyboard_interactive<N: AccessibleNode>(element: &N) -> bool {
+    // Check for keyboard usable pattern, then for text pattern
+    element.supports_value() || element.supports_text()
 }
 
-pub fn has_focus(element: &IUIAutomationElement) -> bool {
-    unsafe {
-        if let Ok(focused) = element.GetCurrentPropertyValue(UIA_HasKeyboardFocusPropertyId) {
-            if focused.Anonymous.Anonymous.vt == VARENUM(VT_BOOL.0) {
-                return focused.Anonymous.Anonymous.Anonymous.boolVal.as_bool();
-            }
-        }
-        false
+pub fn find_actual_interactive_element<B: AccessibilityBackend>(backend: &B, element: &B::Node) -> B::Node {
+    // Check if current element is already interactive
+    if is_interactive_element(element) {
+        return element.clone();
     }
-}
 
-pub fn initialize_automation() -> WindowsResult<IUIAutomation> {
-    unsafe {
-        let automation: IUIAutomation = CoCreateInstance(
-            &CUIAutomation,
-            None,
-            CLSCTX_ALL
-        )?;
-        
-        Ok(automation)
+    // Try to find child interactive elements
+    if let Some(child) = get_interactive_children(backend, element).into_iter().next() {
+        return child;
     }
+
+    // If no interactive element found, return original element
+    element.clone()
 }
 
-pub fn get_element_at_point(
-    automation: &IUIAutomation,
-    point: POINT
-) -> WindowsResult<Option<IUIAutomationElement>> {
-    unsafe {
-        match automation.ElementFromPoint(point) {
-            Ok(element) => Ok(Some(element)),
-            Err(_) => Ok(None)
-        }
-    }
+const INTERACTIVE_CONTROL_TYPES: &[UIA_CONTROLTYPE_ID] = &[
+    UIA_ButtonControlTypeId,
+    UIA_ComboBoxControlTypeId,
+    UIA_MenuItemControlTypeId,
+    UIA_TabItemControlTypeId,
+    UIA_ListItemControlTypeId,
+    UIA_TreeItemControlTypeId,
+    UIA_SpinnerControlTypeId,
+    UIA_HyperlinkControlTypeId,
+];
+
+pub fn is_interactive_element<N: AccessibleNode>(element: &N) -> bool {
+    // Check if it's an interactive control type
+    element.control_type_id().is_some_and(|control_type| {
+        INTERACTIVE_CONTROL_TYPES.iter().any(|interactive| interactive.0 as i32 == control_type)
+    })
 }
 
-pub fn get_automation_and_element(point: POINT) -> WindowsResult<Option<(IUIAutomation, IUIAutomationElement)>> {
-    let automation = initialize_automation()?;
-    
-    if let Some(element) = get_element_at_point(&automation, point)? {
-        Ok(Some((automation, element)))
-    } else {
-        Ok(None)
-    }
-}
\ No newline at end of file
+fn get_interactive_children<B: AccessibilityBackend>(backend: &B, element: &B::Node) -> Vec<B::Node> {
+    backend.children(element).into_iter()
+        .filter(is_interactive_element)
+        .collect()
+}
@@ -0,0 +1,73 @@
+use windows::Win32::UI::Accessibility::{UIA_MenuControlTypeId, UIA_MenuItemControlTypeId};
+use crate::models::element_info::BoundingRect;
+use super::backend::{AccessibilityBackend, AccessibleNode};
+
+/// A menu item as it was while its menu was open.
+#[derive(Debug, Clone)]
+pub struct MenuItemSnapshot {
+    pub name: String,
+    pub bounds: BoundingRect,
+}
+
+impl MenuItemSnapshot {
+    fn read<N: AccessibleNode>(item: &N) -> Option<Self> {
+        Some(Self { name: item.name(), bounds: item.bounds()? })
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        let b = &self.bounds;
+        x >= b.x && x < b.x + b.width && y >= b.y && y < b.y + b.height
+    }
+}
+
+/// An open popup menu. Menus close before a click on one of their items
+/// can be resolved, so the items are read while it is still showing.
+#[derive(Debug, Clone)]
+pub struct OpenMenu {
+    /// The popup's window, to match it up with when it closes
+    pub hwnd: isize,
+    /// The item that opened this menu, e.g. "File" or "Send to"; `None`
+    /// for context menus
+    pub opener: Option<MenuItemSnapshot>,
+    pub items: Vec<MenuItemSnapshot>,
+}
+
+impl OpenMenu {
+    pub fn item_at(&self, x: i32, y: i32) -> Option<&MenuItemSnapshot> {
+        self.items.iter().find(|item| item.contains(x, y))
+    }
+}
+
+fn is_control_type<N: AccessibleNode>(node: &N, control_type: i32) -> bool {
+    node.control_type_id() == Some(control_type)
+}
+
+/// Reads a menu that just opened. `menu` is the popup's element; some
+/// frameworks put the actual menu one level down.
+pub fn snapshot_menu<B: AccessibilityBackend>(backend: &B, menu: &B::Node, hwnd: isize) -> Option<OpenMenu> {
+    let menu_type = UIA_MenuControlTypeId.0 as i32;
+    let item_type = UIA_MenuItemControlTypeId.0 as i32;
+
+    let menu = if is_control_type(menu, menu_type) {
+        menu.clone()
+    } else {
+        backend.children(menu).into_iter().find(|child| is_control_type(child, menu_type))?
+    };
+
+    let items: Vec<MenuItemSnapshot> = backend.children(&menu).iter()
+        .filter(|child| is_control_type(*child, item_type) && !child.is_offscreen())
+        .filter_map(MenuItemSnapshot::read)
+        .collect();
+    if items.is_empty() {
+        return None;
+    }
+
+    // Submenus hang off their item in most frameworks. Win32 popups are
+    // top-level windows instead, but the item that opened them keeps focus.
+    let opener = backend.parent(&menu)
+        .filter(|parent| is_control_type(parent, item_type))
+        .or_else(|| backend.focused_element().filter(|focused| is_control_type(focused, item_type)))
+        .and_then(|item| MenuItemSnapshot::read(&item));
+
+    Some(OpenMenu { hwnd, opener, items })
+}
@@ -0,0 +1,547 @@
+use std::sync::Arc;
+use serde::Deserialize;
+use crate::models::element_info::{AppContext, BoundingRect};
+use crate::models::screen::ScreenContext;
+use super::backend::{AccessibilityBackend, AccessibleNode};
+use super::element::control_type_id;
+use super::window::WindowContext;
+
+/// Marks a node as a top-level window and says which process owns it.
+/// The window title and class are the node's name and class name.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MockWindow {
+    pub hwnd: isize,
+    pub process_id: u32,
+    pub process_name: String,
+    pub executable_path: String,
+    pub file_version: Option<String>,
+    pub product_name: Option<String>,
+    pub file_description: Option<String>,
+}
+
+/// One element and its subtree. Only `control_type` (by name, e.g.
+/// "Button") is needed; everything else defaults to an enabled, on-screen
+/// element with no text and no patterns.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MockNodeSpec {
+    pub control_type: String,
+    pub name: String,
+    pub automation_id: String,
+    pub class_name: String,
+    pub help_text: String,
+    pub value: String,
+    pub disabled: bool,
+    pub selected: bool,
+    pub toggle_state: Option<i32>,
+    pub offscreen: bool,
+    pub password: bool,
+    pub focusable: bool,
+    pub focused: bool,
+    pub value_pattern: bool,
+    pub text_pattern: bool,
+    pub scroll_pattern: bool,
+    pub bounds: Option<BoundingRect>,
+    pub window: Option<MockWindow>,
+    pub children: Vec<MockNodeSpec>,
+}
+
+/// A whole desktop, e.g.
+///
+/// ```json
+/// {
+///   "foreground_window_title": "Untitled - Notepad",
+///   "root": { "control_type": "Pane", "name": "Desktop", "children": [
+///     { "control_type": "Window", "name": "Untitled - Notepad",
+///       "window": { "hwnd": 1, "process_name": "notepad.exe" },
+///       "bounds": { "x": 0, "y": 0, "width": 800, "height": 600 },
+///       "children": [ ... ] }
+///   ] }
+/// }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockTreeSpec {
+    #[serde(default)]
+    pub foreground_window_title: Option<String>,
+    #[serde(default = "default_screen")]
+    pub screen: ScreenContext,
+    pub root: MockNodeSpec,
+}
+
+fn default_screen() -> ScreenContext {
+    ScreenContext { width: 1920, height: 1080 }
+}
+
+#[derive(Debug)]
+struct MockEntry {
+    // Children live in `MockTree::entries`; this one's are left empty
+    spec: MockNodeSpec,
+    control_type_id: Option<i32>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct MockTree {
+    foreground_window_title: Option<String>,
+    screen: ScreenContext,
+    // Depth-first order, so the root is at 0
+    entries: Vec<MockEntry>,
+}
+
+/// A node of a `MockBackend` tree.
+#[derive(Debug, Clone)]
+pub struct MockNode {
+    tree: Arc<MockTree>,
+    index: usize,
+}
+
+impl MockNode {
+    fn spec(&self) -> &MockNodeSpec {
+        &self.tree.entries[self.index].spec
+    }
+
+    fn at(&self, index: usize) -> MockNode {
+        MockNode { tree: self.tree.clone(), index }
+    }
+}
+
+impl AccessibleNode for MockNode {
+    fn control_type_id(&self) -> Option<i32> {
+        self.tree.entries[self.index].control_type_id
+    }
+
+    fn name(&self) -> String {
+        self.spec().name.clone()
+    }
+
+    fn automation_id(&self) -> String {
+        self.spec().automation_id.clone()
+    }
+
+    fn class_name(&self) -> String {
+        self.spec().class_name.clone()
+    }
+
+    fn help_text(&self) -> String {
+        self.spec().help_text.clone()
+    }
+
+    fn value(&self) -> String {
+        self.spec().value.clone()
+    }
+
+    fn is_enabled(&self) -> bool {
+        !self.spec().disabled
+    }
+
+    fn is_selected(&self) -> bool {
+        self.spec().selected
+    }
+
+    fn toggle_state(&self) -> Option<i32> {
+        self.spec().toggle_state
+    }
+
+    fn is_offscreen(&self) -> bool {
+        self.spec().offscreen
+    }
+
+    fn is_password(&self) -> bool {
+        self.spec().password
+    }
+
+    fn is_focusable(&self) -> bool {
+        self.spec().focusable
+    }
+
+    fn has_focus(&self) -> bool {
+        self.spec().focused
+    }
+
+    fn supports_value(&self) -> bool {
+        self.spec().value_pattern
+    }
+
+    fn supports_text(&self) -> bool {
+        self.spec().text_pattern
+    }
+
+    fn supports_scroll(&self) -> bool {
+        self.spec().scroll_pattern
+    }
+
+    fn bounds(&self) -> Option<BoundingRect> {
+        self.spec().bounds.filter(|bounds| bounds.width > 0 && bounds.height > 0)
+    }
+}
+
+/// In-memory accessibility tree described in JSON, for exercising element
+/// resolution without a desktop. Hit testing picks the deepest on-screen
+/// node whose bounds contain the point, earlier siblings first.
+#[derive(Debug, Clone)]
+pub struct MockBackend {
+    root: MockNode,
+}
+
+impl MockBackend {
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        Ok(Self::new(serde_json::from_str(json)?))
+    }
+
+    pub fn new(spec: MockTreeSpec) -> Self {
+        let mut entries = Vec::new();
+        flatten(spec.root, None, &mut entries);
+        let tree = MockTree {
+            foreground_window_title: spec.foreground_window_title,
+            screen: spec.screen,
+            entries,
+        };
+        Self { root: MockNode { tree: Arc::new(tree), index: 0 } }
+    }
+
+    fn entries(&self) -> &[MockEntry] {
+        &self.root.tree.entries
+    }
+
+    /// The nearest node at or above `node` that is a window, and its spec.
+    fn window_node(&self, node: &MockNode) -> Option<(&MockNodeSpec, &MockWindow)> {
+        let mut index = Some(node.index);
+        while let Some(current) = index {
+            let entry = &self.entries()[current];
+            if let Some(window) = &entry.spec.window {
+                return Some((&entry.spec, window));
+            }
+            index = entry.parent;
+        }
+        None
+    }
+}
+
+fn flatten(mut spec: MockNodeSpec, parent: Option<usize>, entries: &mut Vec<MockEntry>) -> usize {
+    let index = entries.len();
+    let children = std::mem::take(&mut spec.children);
+    let control_type_id = control_type_id(&spec.control_type);
+    entries.push(MockEntry { spec, control_type_id, parent, children: Vec::new() });
+    for child in children {
+        let child_index = flatten(child, Some(index), entries);
+        entries[index].children.push(child_index);
+    }
+    index
+}
+
+fn contains(bounds: &BoundingRect, x: i32, y: i32) -> bool {
+    x >= bounds.x && x < bounds.x + bounds.width && y >= bounds.y && y < bounds.y + bounds.height
+}
+
+impl AccessibilityBackend for MockBackend {
+    type Node = MockNode;
+
+    fn element_from_point(&self, x: i32, y: i32) -> Option<Self::Node> {
+        let mut current = self.root.clone();
+        while let Some(child) = self.children(&current).into_iter()
+            .find(|child| !child.is_offscreen() && child.bounds().is_some_and(|bounds| contains(&bounds, x, y)))
+        {
+            current = child;
+        }
+        Some(current)
+    }
+
+    fn focused_element(&self) -> Option<Self::Node> {
+        self.entries().iter()
+            .position(|entry| entry.spec.focused)
+            .map(|index| self.root.at(index))
+    }
+
+    fn root(&self) -> Option<Self::Node> {
+        Some(self.root.clone())
+    }
+
+    fn parent(&self, node: &Self::Node) -> Option<Self::Node> {
+        self.entries()[node.index].parent.map(|index| self.root.at(index))
+    }
+
+    fn children(&self, node: &Self::Node) -> Vec<Self::Node> {
+        self.entries()[node.index].children.iter()
+            .map(|&index| self.root.at(index))
+            .collect()
+    }
+
+    fn is_same_element(&self, a: &Self::Node, b: &Self::Node) -> bool {
+        Arc::ptr_eq(&a.tree, &b.tree) && a.index == b.index
+    }
+
+    fn window_of(&self, node: &Self::Node) -> Option<WindowContext> {
+        let (spec, window) = self.window_node(node)?;
+        Some(WindowContext {
+            hwnd: window.hwnd,
+            owner_hwnd: window.hwnd,
+            process_id: window.process_id,
+            process_name: window.process_name.clone(),
+            executable_path: window.executable_path.clone(),
+            window_class: spec.class_name.clone(),
+            window_title: spec.name.clone(),
+        })
+    }
+
+    fn process_name(&self, node: &Self::Node) -> String {
+        self.window_node(node)
+            .map(|(_, window)| window.process_name.clone())
+            .unwrap_or_default()
+    }
+
+    fn app_context(&self, window: WindowContext) -> AppContext {
+        let version = self.entries().iter()
+            .filter_map(|entry| entry.spec.window.as_ref())
+            .find(|spec| spec.hwnd == window.hwnd);
+        AppContext {
+            process_id: window.process_id,
+            process_name: window.process_name,
+            executable_path: window.executable_path,
+            file_version: version.and_then(|spec| spec.file_version.clone()),
+            product_name: version.and_then(|spec| spec.product_name.clone()),
+            file_description: version.and_then(|spec| spec.file_description.clone()),
+            window_handle: window.hwnd,
+            window_class: window.window_class,
+        }
+    }
+
+    fn foreground_window_title(&self) -> Option<String> {
+        self.root.tree.foreground_window_title.clone()
+    }
+
+    fn screen_context(&self) -> ScreenContext {
+        self.root.tree.screen.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_automation::element::{
+        find_actual_interactive_element, find_visible_tooltip, get_element_info, get_scroll_container_info,
+        is_interactive_element,
+    };
+    use crate::ui_automation::selector::{build_selector, resolve_selector};
+
+    const DESKTOP: &str = r#"{
+        "foreground_window_title": "Untitled - Notepad",
+        "root": { "control_type": "Pane", "name": "Desktop", "children": [
+            { "control_type": "Window", "name": "Untitled - Notepad", "class_name": "Notepad",
+              "window": { "hwnd": 1, "process_id": 10, "process_name": "notepad.exe", "product_name": "Notepad" },
+              "bounds": { "x": 0, "y": 0, "width": 800, "height": 600 },
+              "children": [
+                { "control_type": "ToolBar", "name": "Formatting",
+                  "bounds": { "x": 0, "y": 0, "width": 800, "height": 40 },
+                  "children": [
+                    { "control_type": "Button", "name": "Bold",
+                      "bounds": { "x": 0, "y": 0, "width": 40, "height": 40 },
+                      "children": [
+                        { "control_type": "Text", "name": "B",
+                          "bounds": { "x": 10, "y": 10, "width": 20, "height": 20 } }
+                      ] },
+                    { "control_type": "Button", "name": "Italic", "disabled": true,
+                      "bounds": { "x": 40, "y": 0, "width": 40, "height": 40 } },
+                    { "control_type": "CheckBox", "name": "Word wrap", "toggle_state": 1,
+                      "bounds": { "x": 80, "y": 0, "width": 80, "height": 40 } }
+                  ] },
+                { "control_type": "Pane", "name": "Tabs",
+                  "bounds": { "x": 0, "y": 40, "width": 800, "height": 40 },
+                  "children": [
+                    { "control_type": "TabItem", "name": "notes.txt",
+                      "bounds": { "x": 0, "y": 40, "width": 100, "height": 40 } }
+                  ] },
+                { "control_type": "List", "name": "Files", "scroll_pattern": true,
+                  "bounds": { "x": 0, "y": 80, "width": 300, "height": 500 },
+                  "children": [
+                    { "control_type": "ListItem", "name": "a.txt",
+                      "bounds": { "x": 0, "y": 80, "width": 300, "height": 20 } },
+                    { "control_type": "ListItem", "name": "b.txt", "selected": true,
+                      "bounds": { "x": 0, "y": 100, "width": 300, "height": 20 } }
+                  ] },
+                { "control_type": "Document", "name": "Text editor", "value": "hello",
+                  "value_pattern": true, "text_pattern": true,
+                  "bounds": { "x": 300, "y": 80, "width": 500, "height": 500 },
+                  "children": [
+                    { "control_type": "Text", "name": "Placeholder", "offscreen": true,
+                      "bounds": { "x": 300, "y": 80, "width": 100, "height": 20 } }
+                  ] }
+              ] },
+            { "control_type": "Window", "name": "Calculator", "class_name": "ApplicationFrameWindow",
+              "window": { "hwnd": 2, "process_id": 20, "process_name": "calc.exe" },
+              "bounds": { "x": 900, "y": 0, "width": 300, "height": 400 },
+              "children": [
+                { "control_type": "Button", "name": "One", "automation_id": "num1Button",
+                  "bounds": { "x": 900, "y": 100, "width": 50, "height": 50 } },
+                { "control_type": "Pane", "name": "Memory",
+                  "bounds": { "x": 900, "y": 200, "width": 300, "height": 100 },
+                  "children": [
+                    { "control_type": "ToolTip", "name": "Memory clear", "offscreen": true },
+                    { "control_type": "ToolTip", "name": "Memory recall",
+                      "bounds": { "x": 950, "y": 250, "width": 100, "height": 20 } }
+                  ] }
+              ] }
+        ] }
+    }"#;
+
+    fn desktop() -> MockBackend {
+        MockBackend::from_json(DESKTOP).unwrap()
+    }
+
+    /// The raw node under a point, as a hit test reports it.
+    fn node_at(backend: &MockBackend, x: i32, y: i32) -> MockNode {
+        backend.element_from_point(x, y).unwrap()
+    }
+
+    #[test]
+    fn click_on_a_label_resolves_to_its_button() {
+        let backend = desktop();
+        let info = get_element_info(&backend, 15, 15).unwrap();
+
+        assert_eq!(info.name, "Bold");
+        assert_eq!(info.control_type, "Button");
+        assert_eq!(info.parent_name, "Formatting");
+        assert_eq!(info.window_title, "Untitled - Notepad");
+        assert_eq!((info.x, info.y), (Some(15), Some(15)));
+        assert_eq!(info.bounds, Some(BoundingRect { x: 0, y: 0, width: 40, height: 40 }));
+        let app = info.app.unwrap();
+        assert_eq!(app.process_name, "notepad.exe");
+        assert_eq!(app.window_handle, 1);
+        assert_eq!(app.product_name.as_deref(), Some("Notepad"));
+        let ancestors: Vec<&str> = info.ancestors.iter().map(|ancestor| ancestor.name.as_str()).collect();
+        assert_eq!(ancestors, ["Formatting", "Untitled - Notepad"]);
+    }
+
+    #[test]
+    fn element_info_reports_state() {
+        let backend = desktop();
+        assert_eq!(get_element_info(&backend, 50, 10).unwrap().state, "disabled");
+        assert_eq!(get_element_info(&backend, 100, 10).unwrap().state, "checked");
+        assert_eq!(get_element_info(&backend, 10, 105).unwrap().state, "selected");
+        assert_eq!(get_element_info(&backend, 15, 15).unwrap().state, "");
+    }
+
+    #[test]
+    fn text_fields_are_kept_and_offscreen_nodes_skipped() {
+        let backend = desktop();
+        let info = get_element_info(&backend, 310, 90).unwrap();
+
+        assert_eq!(info.name, "Text editor");
+        assert_eq!(info.control_type, "Document");
+        assert_eq!(info.value, "hello");
+    }
+
+    #[test]
+    fn empty_pane_is_resolved_to_its_interactive_child() {
+        let backend = desktop();
+        let pane = node_at(&backend, 500, 60);
+        assert_eq!(pane.name(), "Tabs");
+
+        let found = find_actual_interactive_element(&backend, &pane);
+        assert_eq!(found.name(), "notes.txt");
+        assert_eq!(get_element_info(&backend, 500, 60).unwrap().name, "notes.txt");
+    }
+
+    #[test]
+    fn interactive_or_childless_elements_are_kept() {
+        let backend = desktop();
+        let button = node_at(&backend, 50, 10);
+        assert!(backend.is_same_element(&find_actual_interactive_element(&backend, &button), &button));
+
+        let document = node_at(&backend, 310, 90);
+        assert!(backend.is_same_element(&find_actual_interactive_element(&backend, &document), &document));
+    }
+
+    #[test]
+    fn interactive_control_types() {
+        let backend = desktop();
+        assert!(is_interactive_element(&node_at(&backend, 50, 10)));
+        assert!(is_interactive_element(&node_at(&backend, 50, 60)));
+        assert!(is_interactive_element(&node_at(&backend, 10, 85)));
+        assert!(!is_interactive_element(&node_at(&backend, 15, 15)));
+        assert!(!is_interactive_element(&node_at(&backend, 100, 10)));
+        assert!(!is_interactive_element(&node_at(&backend, 310, 90)));
+    }
+
+    #[test]
+    fn scroll_resolves_to_the_scrolling_ancestor() {
+        let backend = desktop();
+        let info = get_scroll_container_info(&backend, 10, 105).unwrap();
+        assert_eq!(info.name, "Files");
+        assert_eq!(info.control_type, "List");
+        assert_eq!((info.x, info.y), (Some(10), Some(105)));
+    }
+
+    #[test]
+    fn scroll_without_a_scroller_uses_the_node_under_the_cursor() {
+        let backend = desktop();
+        let info = get_scroll_container_info(&backend, 15, 15).unwrap();
+        assert_eq!(info.name, "B");
+        assert_eq!(info.control_type, "Text");
+    }
+
+    #[test]
+    fn selector_counts_siblings_of_the_same_type() {
+        let backend = desktop();
+        let selector = build_selector(&backend, &node_at(&backend, 50, 10)).unwrap();
+
+        assert_eq!(selector.process_name, "notepad.exe");
+        let path: Vec<(&str, &str, usize)> = selector.path.iter()
+            .map(|segment| (segment.control_type.as_str(), segment.name.as_str(), segment.index))
+            .collect();
+        assert_eq!(path, [
+            ("Window", "Untitled - Notepad", 0),
+            ("ToolBar", "Formatting", 0),
+            ("Button", "Italic", 1),
+        ]);
+        assert_eq!(selector.path[0].class_name, "Notepad");
+    }
+
+    #[test]
+    fn selector_leaves_top_level_windows_unindexed() {
+        let backend = desktop();
+        let button = node_at(&backend, 910, 110);
+        let selector = build_selector(&backend, &button).unwrap();
+
+        assert_eq!(selector.process_name, "calc.exe");
+        assert_eq!(selector.path.len(), 2);
+        assert_eq!(selector.path[0].name, "Calculator");
+        assert_eq!(selector.path[0].index, 0);
+        assert_eq!(selector.path[1].automation_id, "num1Button");
+    }
+
+    #[test]
+    fn selector_resolves_back_to_its_element() {
+        let backend = desktop();
+        for (x, y) in [(50, 10), (10, 105), (310, 90), (910, 110)] {
+            let node = node_at(&backend, x, y);
+            let selector = build_selector(&backend, &node).unwrap();
+            let found = resolve_selector(&backend, &selector).unwrap();
+            assert!(backend.is_same_element(&found, &node), "{} at ({}, {})", node.name(), x, y);
+        }
+    }
+
+    #[test]
+    fn selector_survives_a_retitled_window() {
+        let backend = desktop();
+        let node = node_at(&backend, 50, 10);
+        let mut selector = build_selector(&backend, &node).unwrap();
+        selector.path[0].name = "notes.txt - Notepad".to_string();
+
+        let found = resolve_selector(&backend, &selector).unwrap();
+        assert!(backend.is_same_element(&found, &node));
+    }
+
+    #[test]
+    fn tooltip_nested_in_the_hovered_window_is_found() {
+        let backend = desktop();
+        let root = backend.root().unwrap();
+        let windows = backend.children(&root);
+        let (notepad, calculator) = (&windows[0], &windows[1]);
+
+        assert_eq!(find_visible_tooltip(&backend, Some(calculator)).as_deref(), Some("Memory recall"));
+        // Another window's tooltip isn't the one shown for this hover
+        assert_eq!(find_visible_tooltip(&backend, Some(notepad)), None);
+    }
+}
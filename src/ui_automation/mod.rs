@@ -1,6 +1,12 @@
+pub mod automation;
+pub mod backend;
 pub mod element;
 pub mod window;
 pub mod utils;
+pub mod process;
+pub mod selector;
+pub mod menu;
+pub mod mock;
 
 pub const VK_LBUTTON: i32 = 0x01;
 pub const VK_RETURN: i32 = 0x0D;
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::ffi::c_void;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use windows::core::{HSTRING, PWSTR};
+use windows::Win32::Foundation::CloseHandle;
+use windows::Win32::Storage::FileSystem::{
+    GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW, VS_FIXEDFILEINFO,
+};
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+
+/// Full path of the process executable, e.g. `C:\Program Files\KeePass\KeePass.exe`.
+/// Limited query rights are enough even for elevated processes.
+pub fn process_image_path(process_id: u32) -> Option<String> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let ok = QueryFullProcessImageNameW(handle, PROCESS_NAME_WIN32, PWSTR(buffer.as_mut_ptr()), &mut len);
+        CloseHandle(handle);
+        if !ok.as_bool() {
+            return None;
+        }
+        Some(String::from_utf16_lossy(&buffer[..len as usize]))
+    }
+}
+
+pub fn process_name_from_path(path: &str) -> String {
+    path.rsplit(['\\', '/']).next().unwrap_or(path).to_string()
+}
+
+/// Fields from an executable's version resource.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutableVersion {
+    pub file_version: Option<String>,
+    pub product_name: Option<String>,
+    pub file_description: Option<String>,
+}
+
+// Reading version resources hits the disk and they don't change while we run
+static VERSION_CACHE: Lazy<Mutex<HashMap<String, ExecutableVersion>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn executable_version(path: &str) -> ExecutableVersion {
+    if path.is_empty() {
+        return ExecutableVersion::default();
+    }
+    if let Some(version) = VERSION_CACHE.lock().get(path) {
+        return version.clone();
+    }
+    let version = read_version_resource(path).unwrap_or_default();
+    VERSION_CACHE.lock().insert(path.to_string(), version.clone());
+    version
+}
+
+unsafe fn query_version_value(data: &[u8], sub_block: &str) -> Option<(*const c_void, u32)> {
+    let mut buffer: *mut c_void = std::ptr::null_mut();
+    let mut len = 0u32;
+    let ok = VerQueryValueW(data.as_ptr() as *const c_void, &HSTRING::from(sub_block), &mut buffer, &mut len);
+    (ok.as_bool() && !buffer.is_null() && len > 0).then_some((buffer as *const c_void, len))
+}
+
+fn read_version_resource(path: &str) -> Option<ExecutableVersion> {
+    unsafe {
+        let path = HSTRING::from(path);
+        let size = GetFileVersionInfoSizeW(&path, None);
+        if size == 0 {
+            return None;
+        }
+        let mut data = vec![0u8; size as usize];
+        if !GetFileVersionInfoW(&path, 0, size, data.as_mut_ptr() as *mut c_void).as_bool() {
+            return None;
+        }
+
+        let file_version = query_version_value(&data, "\\").and_then(|(buffer, len)| {
+            if (len as usize) < std::mem::size_of::<VS_FIXEDFILEINFO>() {
+                return None;
+            }
+            let info = &*(buffer as *const VS_FIXEDFILEINFO);
+            Some(format!(
+                "{}.{}.{}.{}",
+                info.dwFileVersionMS >> 16,
+                info.dwFileVersionMS & 0xffff,
+                info.dwFileVersionLS >> 16,
+                info.dwFileVersionLS & 0xffff,
+            ))
+        });
+
+        // Strings are stored per language; use the first one the file lists
+        let translation = query_version_value(&data, "\\VarFileInfo\\Translation").and_then(|(buffer, len)| {
+            if len < 4 {
+                return None;
+            }
+            let pair = std::slice::from_raw_parts(buffer as *const u16, 2);
+            Some(format!("{:04x}{:04x}", pair[0], pair[1]))
+        });
+        let string_value = |name: &str| {
+            let translation = translation.as_ref()?;
+            let sub_block = format!("\\StringFileInfo\\{}\\{}", translation, name);
+            // For strings the length is in characters, including the terminator
+            let (buffer, len) = query_version_value(&data, &sub_block)?;
+            let chars = std::slice::from_raw_parts(buffer as *const u16, len as usize);
+            let value = String::from_utf16_lossy(chars).trim_end_matches('\0').trim().to_string();
+            (!value.is_empty()).then_some(value)
+        };
+
+        Some(ExecutableVersion {
+            file_version,
+            product_name: string_value("ProductName"),
+            file_description: string_value("FileDescription"),
+        })
+    }
+}
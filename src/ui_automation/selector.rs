@@ -0,0 +1,122 @@
+use crate::models::element_info::ElementInfo;
+use crate::models::selector::{ElementSelector, SelectorSegment};
+use super::backend::{AccessibilityBackend, AccessibleNode};
+use super::element::{build_element_info, control_type_id, control_type_name};
+
+// Deeper trees than this are almost certainly a cycle in a broken provider
+const MAX_SELECTOR_DEPTH: usize = 64;
+
+fn read_segment<N: AccessibleNode>(element: &N, index: usize) -> SelectorSegment {
+    SelectorSegment {
+        control_type: element.control_type_id().map(control_type_name).unwrap_or_default(),
+        automation_id: element.automation_id(),
+        name: element.name(),
+        class_name: element.class_name(),
+        index,
+    }
+}
+
+/// Describes the path from the element's top-level window down to it.
+pub fn build_selector<B: AccessibilityBackend>(backend: &B, element: &B::Node) -> Option<ElementSelector> {
+    let root = backend.root()?;
+
+    // Element first, top-level window last, each with its parent
+    let mut chain: Vec<(B::Node, B::Node)> = Vec::new();
+    let mut current = element.clone();
+    loop {
+        let parent = backend.parent(&current)?;
+        let at_top = backend.is_same_element(&parent, &root);
+        chain.push((current, parent.clone()));
+        if at_top {
+            break;
+        }
+        if chain.len() >= MAX_SELECTOR_DEPTH {
+            return None;
+        }
+        current = parent;
+    }
+
+    // Top-level windows get no index: listing them is slow with many open,
+    // and the process name and window class already tell them apart
+    let path = chain.iter().rev().enumerate()
+        .map(|(depth, (element, parent))| {
+            let index = element.control_type_id()
+                .filter(|_| depth > 0)
+                .and_then(|control_type| {
+                    backend.children_of_type(parent, control_type).iter()
+                        .position(|sibling| backend.is_same_element(sibling, element))
+                })
+                .unwrap_or(0);
+            read_segment(element, index)
+        })
+        .collect();
+
+    let top_level = &chain.last()?.0;
+    Some(ElementSelector {
+        process_name: backend.process_name(top_level),
+        path,
+    })
+}
+
+/// Picks the child matching `segment`. Automation ids are meant to be unique
+/// among siblings, so they win; names and classes come next, and the sibling
+/// index settles ties or stands in when names changed (titles, counters).
+fn find_child<B: AccessibilityBackend>(
+    backend: &B,
+    parent: &B::Node,
+    segment: &SelectorSegment,
+    process_name: Option<&str>,
+) -> Option<B::Node> {
+    // Indices count the same siblings `build_selector` did
+    let children = match control_type_id(&segment.control_type) {
+        Some(control_type) => backend.children_of_type(parent, control_type),
+        None => backend.children(parent),
+    };
+    let candidates: Vec<(SelectorSegment, B::Node)> = children.into_iter()
+        .map(|child| (read_segment(&child, 0), child))
+        .filter(|(found, _)| found.control_type == segment.control_type)
+        .enumerate()
+        .map(|(index, (found, child))| (SelectorSegment { index, ..found }, child))
+        .filter(|(_, child)| process_name.is_none_or(|name| backend.process_name(child).eq_ignore_ascii_case(name)))
+        .collect();
+
+    if !segment.automation_id.is_empty() {
+        if let Some((_, child)) = candidates.iter().find(|(found, _)| found.automation_id == segment.automation_id) {
+            return Some(child.clone());
+        }
+    }
+
+    let named: Vec<&(SelectorSegment, B::Node)> = candidates.iter()
+        .filter(|(found, _)| found.name == segment.name && found.class_name == segment.class_name)
+        .collect();
+    if let Some((_, child)) = named.iter().find(|(found, _)| found.index == segment.index).or(named.first()) {
+        return Some(child.clone());
+    }
+
+    candidates.into_iter()
+        .find(|(found, _)| found.index == segment.index)
+        .map(|(_, child)| child)
+}
+
+/// Finds the element a selector was built from, or the closest match to it.
+pub fn resolve_selector<B: AccessibilityBackend>(backend: &B, selector: &ElementSelector) -> Option<B::Node> {
+    let mut current = backend.root()?;
+    for (depth, segment) in selector.path.iter().enumerate() {
+        // Only the top-level window is tied to a process; its children may
+        // be hosted by others (browsers, embedded controls)
+        let process_name = Some(selector.process_name.as_str())
+            .filter(|name| depth == 0 && !name.is_empty());
+        current = find_child(backend, &current, segment, process_name)?;
+    }
+    Some(current)
+}
+
+/// Looks up a recorded element in the live UI, positioned at its center.
+pub fn locate_element<B: AccessibilityBackend>(backend: &B, selector: &ElementSelector) -> Option<ElementInfo> {
+    if selector.path.is_empty() {
+        return None;
+    }
+    let element = resolve_selector(backend, selector)?;
+    let (x, y) = element.bounds()?.center();
+    Some(build_element_info(backend, &element, x, y))
+}
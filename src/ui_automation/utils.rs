@@ -1,4 +1,5 @@
-use windows::Win32::System::Com::{VARIANT, VARENUM, VT_BSTR};
+use std::mem::ManuallyDrop;
+use windows::Win32::System::Com::{VARIANT, VARIANT_0, VARIANT_0_0, VARIANT_0_0_0, VARENUM, VT_BSTR, VT_I4};
 use windows::Win32::UI::WindowsAndMessaging::GetSystemMetrics;
 use windows::Win32::UI::WindowsAndMessaging::{SM_CXSCREEN, SM_CYSCREEN};
 
@@ -12,6 +13,18 @@ pub fn variant_to_string(variant: VARIANT) -> String {
     }
 }
 
+pub fn variant_from_i32(value: i32) -> VARIANT {
+    VARIANT {
+        Anonymous: VARIANT_0 {
+            Anonymous: ManuallyDrop::new(VARIANT_0_0 {
+                vt: VT_I4,
+                Anonymous: VARIANT_0_0_0 { lVal: value },
+                ..Default::default()
+            }),
+        },
+    }
+}
+
 pub fn get_screen_size() -> (i32, i32) {
     unsafe {
         let width = GetSystemMetrics(SM_CXSCREEN);
@@ -1,56 +1,195 @@
 use windows::Win32::UI::Accessibility::*;
-use windows::Win32::System::Com::*;
-use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW};
-use super::utils::variant_to_string;
+use serde::{Serialize, Deserialize};
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM, POINT, RECT};
+use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetAncestor, GetClassNameW, GetForegroundWindow, GetWindowLongW, GetWindowRect,
+    GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible, WindowFromPoint,
+    GA_ROOT, GA_ROOTOWNER, GWL_EXSTYLE, WS_EX_TOOLWINDOW,
+};
+use crate::models::element_info::AppContext;
+use super::automation::Automation;
+use super::backend::{AccessibilityBackend, AccessibleNode};
+use super::process::{executable_version, process_image_path, process_name_from_path};
 
-pub fn get_window_title_for_element(element: &IUIAutomationElement) -> String {
+/// The top-level window a point or element belongs to and the process
+/// that owns it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WindowContext {
+    pub hwnd: isize,
+    /// The window at the top of the owner chain, e.g. the main window a
+    /// dialog belongs to; same as `hwnd` for unowned windows
+    pub owner_hwnd: isize,
+    pub process_id: u32,
+    pub process_name: String,
+    pub executable_path: String,
+    pub window_class: String,
+    pub window_title: String,
+}
+
+pub fn window_context_at(x: i32, y: i32) -> Option<WindowContext> {
     unsafe {
-        if let Ok(automation_id) = element.GetCurrentPropertyValue(UIA_AutomationIdPropertyId) {
-            let auto_id = variant_to_string(automation_id);
-            if auto_id == "StartButton" {
-                return "Windows Taskbar".to_string();
-            }
-            if auto_id.starts_with("Appid:") {
-                if let Ok(name) = element.GetCurrentPropertyValue(UIA_NamePropertyId) {
-                    let name_str = variant_to_string(name);
-                    if let Some(idx) = name_str.find(" - ") {
-                        return name_str[..idx].to_string();
-                    }
-                    return name_str;
+        let hwnd = WindowFromPoint(POINT { x, y });
+        if hwnd.0 == 0 {
+            return None;
+        }
+        window_context(GetAncestor(hwnd, GA_ROOT))
+    }
+}
+
+/// Context of the top-level window `hwnd` is in, or of `hwnd` itself.
+pub fn top_level_window_context(hwnd: isize) -> Option<WindowContext> {
+    unsafe { window_context(GetAncestor(HWND(hwnd), GA_ROOT)) }
+}
+
+pub fn foreground_window_context() -> Option<WindowContext> {
+    unsafe { window_context(GetForegroundWindow()) }
+}
+
+/// Center of a window in screen coordinates, used to pick the monitor to
+/// capture for keyboard steps.
+pub fn window_center(hwnd: isize) -> Option<(i32, i32)> {
+    unsafe {
+        let mut rect = RECT::default();
+        GetWindowRect(HWND(hwnd), &mut rect).ok().ok()?;
+        Some(((rect.left + rect.right) / 2, (rect.top + rect.bottom) / 2))
+    }
+}
+
+/// Visible, titled top-level windows of other processes, in z-order, for
+/// picking what a recording should be scoped to.
+pub fn list_top_level_windows() -> Vec<WindowContext> {
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let handles = &mut *(lparam.0 as *mut Vec<HWND>);
+        handles.push(hwnd);
+        BOOL(1)
+    }
+
+    let mut handles: Vec<HWND> = Vec::new();
+    unsafe {
+        let _ = EnumWindows(Some(collect), LPARAM(&mut handles as *mut Vec<HWND> as isize));
+    }
+
+    let own_process_id = std::process::id();
+    handles.into_iter()
+        .filter(|&hwnd| unsafe {
+            IsWindowVisible(hwnd).as_bool()
+                && GetWindowTextLengthW(hwnd) > 0
+                && GetWindowLongW(hwnd, GWL_EXSTYLE) as u32 & WS_EX_TOOLWINDOW.0 == 0
+        })
+        .filter_map(window_context)
+        .filter(|window| window.process_id != own_process_id)
+        .collect()
+}
+
+pub fn window_context(hwnd: HWND) -> Option<WindowContext> {
+    unsafe {
+        if hwnd.0 == 0 {
+            return None;
+        }
+
+        let mut process_id = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
+
+        let mut class = [0u16; 256];
+        let class_len = GetClassNameW(hwnd, &mut class).max(0) as usize;
+        let mut title = [0u16; 512];
+        let title_len = GetWindowTextW(hwnd, &mut title).max(0) as usize;
+
+        // Protected processes refuse even limited queries; keep the rest
+        let executable_path = process_image_path(process_id).unwrap_or_default();
+
+        let owner = GetAncestor(hwnd, GA_ROOTOWNER);
+        Some(WindowContext {
+            hwnd: hwnd.0,
+            owner_hwnd: if owner.0 == 0 { hwnd.0 } else { owner.0 },
+            process_id,
+            process_name: process_name_from_path(&executable_path),
+            executable_path,
+            window_class: String::from_utf16_lossy(&class[..class_len]),
+            window_title: String::from_utf16_lossy(&title[..title_len]),
+        })
+    }
+}
+
+// Elements inside a window often have no handle of their own
+const MAX_HANDLE_SEARCH_DEPTH: usize = 64;
+
+/// The top-level window an element is in, found through the nearest
+/// ancestor that has a window handle.
+pub fn element_window_context(automation: &Automation, element: &IUIAutomationElement) -> Option<WindowContext> {
+    let mut current = element.clone();
+    for _ in 0..MAX_HANDLE_SEARCH_DEPTH {
+        unsafe {
+            if let Ok(hwnd) = current.CachedNativeWindowHandle() {
+                if hwnd.0 != 0 {
+                    return window_context(GetAncestor(hwnd, GA_ROOT));
                 }
             }
         }
+        current = automation.parent(&current)?;
+    }
+    None
+}
 
-        if let Ok(automation) = CoCreateInstance(&CUIAutomation, None, CLSCTX_ALL) {
-            let automation: IUIAutomation = automation;
-            if let Ok(tree_walker) = automation.ControlViewWalker() {
-                let mut current = element.clone();
-                while let Ok(parent) = tree_walker.GetParentElement(&current) {
-                    if let Ok(control_type_variant) = parent.GetCurrentPropertyValue(UIA_ControlTypePropertyId) {
-                        if control_type_variant.Anonymous.Anonymous.vt == VARENUM(VT_I4.0) {
-                            let control_type_id = control_type_variant.Anonymous.Anonymous.Anonymous.lVal;
-                            if control_type_id == 50032 {
-                                if let Ok(name_variant) = parent.GetCurrentPropertyValue(UIA_NamePropertyId) {
-                                    let title = variant_to_string(name_variant);
-                                    if !title.is_empty() {
-                                        return title;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    current = parent;
-                }
+pub fn app_context(window: WindowContext) -> AppContext {
+    let version = executable_version(&window.executable_path);
+    AppContext {
+        process_id: window.process_id,
+        process_name: window.process_name,
+        executable_path: window.executable_path,
+        file_version: version.file_version,
+        product_name: version.product_name,
+        file_description: version.file_description,
+        window_handle: window.hwnd,
+        window_class: window.window_class,
+    }
+}
+
+/// `window` is the element's top-level window, if already known.
+pub fn get_window_title_for_element<B: AccessibilityBackend>(
+    backend: &B,
+    element: &B::Node,
+    window: Option<&WindowContext>,
+) -> String {
+    let auto_id = element.automation_id();
+    if auto_id == "StartButton" {
+        return "Windows Taskbar".to_string();
+    }
+    if auto_id.starts_with("Appid:") {
+        let name_str = element.name();
+        if let Some(idx) = name_str.find(" - ") {
+            return name_str[..idx].to_string();
+        }
+        return name_str;
+    }
+
+    let mut current = element.clone();
+    while let Some(parent) = backend.parent(&current) {
+        if parent.control_type_id() == Some(UIA_WindowControlTypeId.0 as i32) {
+            let title = parent.name();
+            if !title.is_empty() {
+                return title;
             }
         }
-        
+        current = parent;
+    }
+
+    // The element's own window is a better guess than whatever is in front
+    if let Some(window) = window {
+        if !window.window_title.is_empty() {
+            return window.window_title.clone();
+        }
+    }
+
+    backend.foreground_window_title()
+        .unwrap_or_else(|| "Windows Taskbar".to_string())
+}
+
+pub fn foreground_window_title() -> Option<String> {
+    unsafe {
         let hwnd = GetForegroundWindow();
         let mut title = [0u16; 512];
         let len = GetWindowTextW(hwnd, &mut title);
-        if len > 0 {
-            String::from_utf16_lossy(&title[..len as usize])
-        } else {
-            "Windows Taskbar".to_string()
-        }
+        (len > 0).then(|| String::from_utf16_lossy(&title[..len as usize]))
     }
-}
\ No newline at end of file
+}